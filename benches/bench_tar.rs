@@ -8,6 +8,9 @@ use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use tar::CompressionMode;
 use tar::operations;
+use tar::operations::create::{CreateOptions, FileOperand};
+use tar::operations::extract::ExtractOptions;
+use tar::operations::list::ListOptions;
 use tempfile::TempDir;
 
 fn main() {
@@ -39,16 +42,18 @@ fn collect_files(dir: &Path) -> Vec<PathBuf> {
 /// Build a tar archive at `archive_path` from all files in `source_dir`.
 fn build_archive(archive_path: &Path, source_dir: &Path) {
     let files = collect_files(source_dir);
-    let refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+    let refs: Vec<FileOperand> = files.iter().map(|p| p.as_path().into()).collect();
     let output = File::create(archive_path).unwrap();
     let status_output = io::sink();
     operations::create::create_archive(
         output,
         status_output,
         &refs,
-        true,
-        false,
-        CompressionMode::None,
+        &CreateOptions {
+            allow_absolute: true,
+            compression: CompressionMode::None,
+            ..Default::default()
+        },
     )
     .unwrap();
 }
@@ -67,16 +72,18 @@ fn create_archive_10_files(bencher: divan::Bencher) {
     let archive_path = out.path().join("bench.tar");
 
     bencher.bench_local(|| {
-        let refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let refs: Vec<FileOperand> = files.iter().map(|p| p.as_path().into()).collect();
         let output = File::create(&archive_path).unwrap();
         let status_output = io::sink();
         operations::create::create_archive(
             output,
             status_output,
             &refs,
-            true,
-            false,
-            CompressionMode::None,
+            &CreateOptions {
+                allow_absolute: true,
+                compression: CompressionMode::None,
+                ..Default::default()
+            },
         )
         .unwrap();
     });
@@ -92,16 +99,18 @@ fn create_archive_100_files(bencher: divan::Bencher) {
     let archive_path = out.path().join("bench.tar");
 
     bencher.bench_local(|| {
-        let refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let refs: Vec<FileOperand> = files.iter().map(|p| p.as_path().into()).collect();
         let output = File::create(&archive_path).unwrap();
         let status_output = io::sink();
         operations::create::create_archive(
             output,
             status_output,
             &refs,
-            true,
-            false,
-            CompressionMode::None,
+            &CreateOptions {
+                allow_absolute: true,
+                compression: CompressionMode::None,
+                ..Default::default()
+            },
         )
         .unwrap();
     });
@@ -123,10 +132,12 @@ fn create_archive_directory(bencher: divan::Bencher) {
         operations::create::create_archive(
             output,
             status_output,
-            &[sub.as_path()],
-            true,
-            false,
-            CompressionMode::None,
+            &[sub.as_path().into()],
+            &CreateOptions {
+                allow_absolute: true,
+                compression: CompressionMode::None,
+                ..Default::default()
+            },
         )
         .unwrap();
     });
@@ -146,7 +157,15 @@ fn list_archive_50_files(bencher: divan::Bencher) {
 
     bencher.bench_local(|| {
         let input = File::open(&archive_path).unwrap();
-        operations::list::list_archive(input, &archive_path, false, CompressionMode::None).unwrap();
+        operations::list::list_archive(
+            input,
+            &archive_path,
+            &ListOptions {
+                compression: CompressionMode::None,
+                ..Default::default()
+            },
+        )
+        .unwrap();
     });
 }
 
@@ -160,7 +179,16 @@ fn list_archive_verbose_50_files(bencher: divan::Bencher) {
 
     bencher.bench_local(|| {
         let input = File::open(&archive_path).unwrap();
-        operations::list::list_archive(input, &archive_path, true, CompressionMode::None).unwrap();
+        operations::list::list_archive(
+            input,
+            &archive_path,
+            &ListOptions {
+                verbose: true,
+                compression: CompressionMode::None,
+                ..Default::default()
+            },
+        )
+        .unwrap();
     });
 }
 
@@ -185,8 +213,10 @@ fn extract_archive_20_files(bencher: divan::Bencher) {
             operations::extract::extract_archive(
                 input,
                 &archive_path,
-                false,
-                CompressionMode::None,
+                &ExtractOptions {
+                    compression: CompressionMode::None,
+                    ..Default::default()
+                },
             )
             .unwrap();
         });
@@ -65,18 +65,18 @@ impl<W: Write> ArchiveWriter<W> {
         Ok(Self { inner })
     }
 
-    pub(crate) fn finish(self) -> Result<(), TarError> {
-        match self.inner {
-            ArchiveWriterInner::Plain(mut file) => file.flush().map_err(TarError::from),
+    /// Write out what is left of the archive, and return the stream it
+    /// was written to.
+    pub(crate) fn finish(self) -> Result<W, TarError> {
+        let mut output = match self.inner {
+            ArchiveWriterInner::Plain(file) => file,
             ArchiveWriterInner::Gzip(encoder) => {
-                let mut output = encoder.finish().map_err(TarError::CannotFinalizeArchive)?;
-                output.flush().map_err(TarError::from)
+                encoder.finish().map_err(TarError::CannotFinalizeArchive)?
             }
-            ArchiveWriterInner::Zstd(encoder) => {
-                let mut output = encoder.finish()?;
-                output.flush().map_err(TarError::Io)
-            }
-        }
+            ArchiveWriterInner::Zstd(encoder) => encoder.finish()?,
+        };
+        output.flush()?;
+        Ok(output)
     }
 }
 
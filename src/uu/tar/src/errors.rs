@@ -51,14 +51,66 @@ pub enum TarError {
     #[error("tar: Cannot add file '{path}': {source}")]
     CannotAddFile { path: PathBuf, source: io::Error },
 
+    /// Cannot use the directory given with -C
+    #[error("tar: {path}: Cannot chdir: {source}")]
+    CannotChangeDirectory { path: PathBuf, source: io::Error },
+
     /// Cannot extract an archive entry
     #[error("tar: Cannot extract '{path}': {source}")]
     CannotExtract { path: PathBuf, source: io::Error },
 
+    /// Cannot start the --to-command shell
+    #[error("tar: Cannot run '{command}': {source}")]
+    CannotRunCommand { command: String, source: io::Error },
+
+    /// A --to-command child exited unsuccessfully
+    #[error("tar: {pid}: Child returned status {code}")]
+    ChildFailed { pid: u32, code: i32 },
+
+    /// A --to-command child was killed by a signal
+    #[error("tar: {pid}: Child died with signal {signal}")]
+    ChildKilled { pid: u32, signal: i32 },
+
+    /// Some members could not be extracted; reported once all were tried
+    #[error("tar: Exiting with failure status due to previous errors")]
+    PreviousErrors,
+
+    /// Invalid --transform expression
+    #[error("tar: {0}")]
+    InvalidTransform(String),
+
+    /// Malformed line in an --owner-map or --group-map file
+    #[error("tar: {path}:{line}: Invalid map entry '{entry}'")]
+    InvalidMapEntry {
+        path: PathBuf,
+        line: usize,
+        entry: String,
+    },
+
+    /// A --listed-incremental snapshot file that can't be parsed
+    #[error("tar: {path}: Bad incremental file format")]
+    InvalidSnapshot { path: PathBuf },
+
+    /// Cannot save the --listed-incremental snapshot file
+    #[error("tar: {path}: Cannot write: {source}")]
+    CannotWriteSnapshot { path: PathBuf, source: io::Error },
+
     /// General tar operation error
     #[error("tar: {0}")]
     TarOperationError(String),
 
+    /// Volume label given with -V is longer than a header name holds
+    #[error("tar: Volume label is too long (limit is {limit} bytes)")]
+    LabelTooLong { limit: usize },
+
+    /// Archive has no volume label to check against -V
+    #[error("tar: Archive not labeled to match '{pattern}'")]
+    NotLabeled { pattern: String },
+
+    /// Archive volume label does not match -V
+    #[error("tar: Volume '{label}' does not match '{pattern}'")]
+    LabelMismatch { label: String, pattern: String },
+
     /// Cannot finalize the archive
     #[error("tar: Cannot finalize archive: {0}")]
     CannotFinalizeArchive(io::Error),
@@ -5,23 +5,159 @@
 
 use crate::compression::ArchiveWriter;
 use crate::errors::TarError;
+use crate::operations::check_directory;
+use crate::operations::incremental::{self, Incremental, DUMPDIR};
+use crate::operations::label::label_header;
+use crate::operations::metadata::MetadataOverrides;
+use crate::progress::{Direction, Meter, ProgressOptions};
+use crate::record::{RecordWriter, BLOCK_SIZE, DEFAULT_BLOCKING_FACTOR};
+use crate::transform::{Transform, TransformScope};
 use crate::CompressionMode;
-use std::collections::VecDeque;
+use std::collections::HashMap;
 use std::fs;
-use std::io::{BufWriter, Write};
-use std::path::Component::{self, ParentDir, Prefix, RootDir};
+use std::io::{self, BufWriter, Write};
+use std::path::Component::{self, CurDir, ParentDir, Prefix, RootDir};
 use std::path::{self, Path, PathBuf};
-use tar::Builder;
+use tar::{Builder, EntryType, Header, HeaderMode};
 use uucore::error::UResult;
 
+/// A file operand to add to the archive
+///
+/// `path` is the name given on the command line and is what gets stored in
+/// the archive.  When `directory` is set (via `-C`), `path` is looked up
+/// relative to it instead of the current working directory.
+#[derive(Clone, Copy, Debug)]
+pub struct FileOperand<'a> {
+    pub directory: Option<&'a Path>,
+    pub path: &'a Path,
+}
+
+impl<'a> FileOperand<'a> {
+    /// Location of the operand on the filesystem
+    pub(crate) fn source_path(&self) -> PathBuf {
+        match self.directory {
+            Some(dir) => dir.join(self.path),
+            None => self.path.to_path_buf(),
+        }
+    }
+}
+
+impl<'a> From<&'a Path> for FileOperand<'a> {
+    fn from(path: &'a Path) -> Self {
+        Self {
+            directory: None,
+            path,
+        }
+    }
+}
+
+/// Order in which the contents of a directory are added (`--sort`)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SortOrder {
+    /// The order `read_dir` returns them in
+    #[default]
+    None,
+    /// Byte-wise by file name, independent of the filesystem
+    Name,
+    /// By inode number, which can speed up reading on some filesystems
+    Inode,
+}
+
+/// Only add files changed since a date (`-N`, `--newer-mtime`)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NewerThan {
+    /// Seconds since the epoch
+    pub time: u64,
+    /// Also count inode changes, not just data modifications (`-N`)
+    pub check_ctime: bool,
+}
+
+impl NewerThan {
+    /// Whether the file was changed on or after the date.
+    fn includes(&self, meta: &fs::Metadata) -> bool {
+        let date = (self.time, 0);
+        incremental::modified(meta) >= date
+            || (self.check_ctime && incremental::changed(meta) >= date)
+    }
+}
+
+/// Options controlling archive creation
+#[derive(Debug)]
+pub struct CreateOptions {
+    /// Keep leading `/` in member names (`-P`)
+    pub allow_absolute: bool,
+    /// Print each file as it is added (`-v`)
+    pub verbose: bool,
+    /// Compression applied to the archive stream
+    pub compression: CompressionMode,
+    /// Rewrite member names (`--transform`)
+    pub transform: Transform,
+    /// Print transformed rather than original names under `-v`
+    pub show_transformed_names: bool,
+    /// Owner, group, mode and mtime to store instead of those on disk
+    pub overrides: MetadataOverrides,
+    /// Order of directory contents (`--sort`)
+    pub sort: SortOrder,
+    /// Archive the files symlinks point to instead of the links (`-h`)
+    pub dereference: bool,
+    /// Store every hard link as a copy of its data (`--hard-dereference`)
+    pub hard_dereference: bool,
+    /// Warn about files whose links were not all archived (`-l`)
+    pub check_links: bool,
+    /// Add the contents of directories, not just their entries
+    /// (`--recursion`, the default; `--no-recursion` turns it off)
+    pub recursion: bool,
+    /// Don't descend into directories on other filesystems
+    /// (`--one-file-system`)
+    pub one_file_system: bool,
+    /// Snapshot file for a GNU incremental dump (`-g`)
+    pub listed_incremental: Option<PathBuf>,
+    /// Leave out files older than this; directories are always added
+    pub newer: Option<NewerThan>,
+    /// Delete files once the archive is complete (`--remove-files`)
+    pub remove_files: bool,
+    /// Volume label written as the first record (`-V`)
+    pub label: Option<String>,
+    /// Blocks per record; the archive is written, and padded, in whole
+    /// records (`-b`)
+    pub blocking_factor: usize,
+    /// Totals and checkpoints to report (`--totals`, `--checkpoint`)
+    pub progress: ProgressOptions,
+}
+
+impl Default for CreateOptions {
+    fn default() -> Self {
+        Self {
+            allow_absolute: false,
+            verbose: false,
+            compression: CompressionMode::None,
+            transform: Transform::default(),
+            show_transformed_names: false,
+            overrides: MetadataOverrides::default(),
+            sort: SortOrder::None,
+            dereference: false,
+            hard_dereference: false,
+            check_links: false,
+            recursion: true,
+            one_file_system: false,
+            listed_incremental: None,
+            newer: None,
+            remove_files: false,
+            label: None,
+            blocking_factor: DEFAULT_BLOCKING_FACTOR,
+            progress: ProgressOptions::default(),
+        }
+    }
+}
+
 /// Create a tar archive from the specified files
 ///
 /// # Arguments
 ///
 /// * `output` - Destination where the tar archive should be written
-/// * `files` - Slice of file paths to add to the archive
-/// * `allow_absolute` - Allow absolute paths while creating archive
-/// * `verbose` - Whether to print verbose output during creation
+/// * `status_output` - Destination for verbose listing
+/// * `files` - Slice of file operands to add to the archive
+/// * `options` - Flags controlling how members are named and stored
 ///
 /// # Errors
 ///
@@ -29,81 +165,454 @@ use uucore::error::UResult;
 /// - The archive file cannot be created
 /// - Any input file cannot be read
 /// - Files cannot be added due to I/O or permission errors
+/// - Some archived files could not be removed under `--remove-files`; each
+///   failure has already been reported
 pub fn create_archive(
     output: impl Write,
     status_output: impl Write,
-    files: &[&Path],
-    allow_absolute: bool,
-    verbose: bool,
-    compression: CompressionMode,
+    files: &[FileOperand<'_>],
+    options: &CreateOptions,
 ) -> UResult<()> {
-    let output = BufWriter::new(output);
+    let record_size = options.blocking_factor * BLOCK_SIZE;
+    let output = BufWriter::with_capacity(record_size, output);
+    let output = Meter::new(output, &options.progress, Direction::Write);
     let mut status_output = BufWriter::new(status_output);
 
     // Create Builder instance
-    let writer = ArchiveWriter::new(output, compression)?;
-    let mut builder = Builder::new(writer);
-    builder.preserve_absolute(allow_absolute);
+    let writer = ArchiveWriter::new(output, options.compression)?;
+    let mut builder = Builder::new(RecordWriter::new(writer, record_size));
+    builder.preserve_absolute(options.allow_absolute);
+    let mut state = CreateState {
+        links: LinkTable::new(),
+        incremental: options
+            .listed_incremental
+            .as_deref()
+            .map(Incremental::start)
+            .transpose()?,
+        archived: Vec::new(),
+    };
+
+    if let Some(label) = &options.label {
+        builder
+            .append(&label_header(label)?, io::empty())
+            .map_err(TarError::Io)?;
+    }
 
     // Add each file or directory to the archive
-    for &path in files {
-        // Check if path exists
-        if !path.exists() {
+    for operand in files {
+        if let Some(dir) = operand.directory {
+            check_directory(dir)?;
+        }
+
+        let path = operand.path;
+        let source = operand.source_path();
+
+        // Check if path exists; a dangling symlink is still a member
+        if fs::symlink_metadata(&source).is_err() {
             return Err(TarError::FileNotFound {
                 path: path.to_path_buf(),
             }
             .into());
         }
 
-        if verbose {
-            print_verbose_tree(&mut status_output, path)?;
-        }
-
-        let normalized_name = get_normalized_path(path, allow_absolute)?;
+        let normalized_name = get_normalized_path(path, options.allow_absolute)?;
+        append_tree(
+            &mut builder,
+            &mut status_output,
+            &source,
+            path,
+            &normalized_name,
+            &mut state,
+            options,
+        )?;
+    }
 
-        // If it's a directory, recursively add all contents
-        if path.is_dir() {
-            builder.append_dir_all(normalized_name, path).map_err(|e| {
-                TarError::CannotAddDirectory {
-                    path: path.to_path_buf(),
-                    source: e,
-                }
-            })?;
-        } else {
-            // For files, add them directly
-            builder
-                .append_path_with_name(path, normalized_name)
-                .map_err(|e| TarError::CannotAddFile {
-                    path: path.to_path_buf(),
-                    source: e,
-                })?;
-        }
+    if options.check_links {
+        report_missing_links(&state.links)?;
     }
 
     builder.finish().map_err(TarError::CannotFinalizeArchive)?;
     let writer = builder
         .into_inner()
         .map_err(|e| TarError::TarOperationError(format!("Failed to finalize archive: {e}")))?;
-    writer.finish()?;
+    writer
+        .finish()
+        .map_err(TarError::CannotFinalizeArchive)?
+        .finish()?
+        .finish()
+        .map_err(TarError::Io)?;
     status_output.flush().map_err(TarError::Io)?;
+    if let Some(incremental) = &state.incremental {
+        incremental.finish()?;
+    }
+
+    // Only now that the archive is complete is it safe to delete anything
+    if options.remove_files && !remove_archived(&state.archived).map_err(TarError::Io)? {
+        return Err(TarError::PreviousErrors.into());
+    }
+    Ok(())
+}
+
+/// Delete the files that went into the archive, each directory after its
+/// contents, reporting every failure.  Returns whether all were removed.
+fn remove_archived(archived: &[ArchivedFile]) -> io::Result<bool> {
+    let mut stderr = io::stderr().lock();
+    let mut all_removed = true;
+    // Directories are archived before their contents, so go backwards
+    for file in archived.iter().rev() {
+        let (result, action) = if file.is_dir {
+            (fs::remove_dir(&file.source), "rmdir")
+        } else {
+            (fs::remove_file(&file.source), "unlink")
+        };
+        if let Err(e) = result {
+            writeln!(
+                stderr,
+                "tar: {}: Cannot {action}: {e}",
+                file.display.display()
+            )?;
+            all_removed = false;
+        }
+    }
+    Ok(all_removed)
+}
+
+/// A file with more than one link, as first stored in the archive
+struct LinkedFile {
+    /// Member name of the first link archived
+    name: PathBuf,
+    /// Links to the file not seen yet
+    missing: u64,
+}
+
+/// Files with more than one link that are already in the archive, keyed by
+/// device and inode
+type LinkTable = HashMap<(u64, u64), LinkedFile>;
+
+/// A file whose member has been written, for `--remove-files`
+struct ArchivedFile {
+    source: PathBuf,
+    /// Name used in messages
+    display: PathBuf,
+    is_dir: bool,
+}
 
+/// What is remembered across operands while an archive is created
+struct CreateState {
+    links: LinkTable,
+    /// Set for `-g` dumps
+    incremental: Option<Incremental>,
+    /// Files added so far, in order; only kept under `--remove-files`
+    archived: Vec<ArchivedFile>,
+}
+
+/// Warn about every file in `links` with links that were not archived, as
+/// `--check-links` asks.
+fn report_missing_links(links: &LinkTable) -> io::Result<()> {
+    let mut missing: Vec<&Path> = links
+        .values()
+        .filter(|file| file.missing > 0)
+        .map(|file| file.name.as_path())
+        .collect();
+    missing.sort();
+
+    let mut stderr = io::stderr().lock();
+    for name in missing {
+        writeln!(stderr, "tar: Missing links to '{}'.", name.display())?;
+    }
     Ok(())
 }
 
-fn print_verbose_tree(status_output: &mut impl Write, path: &Path) -> UResult<()> {
-    let to_print = get_tree(path)?
-        .iter()
-        .map(|p| (p.is_dir(), p.display().to_string()))
-        .map(|(is_dir, path)| {
+/// Add `source` to the archive as `name`, recursing into directories.
+///
+/// Children are visited depth-first in `--sort` order, and each member's
+/// name is passed through `--transform` before it is stored.  `display` is
+/// the name as given on the command line, used for verbose output.  Later
+/// links to a file already in `state.links` are stored as hard links to it.
+///
+/// With `--one-file-system`, directories on another device than `source`
+/// are stored without their contents.  In an incremental dump, directories
+/// are stored as dumpdir members and only the files `state.incremental`
+/// selects are added; `--newer` likewise leaves out older files.
+fn append_tree<W: Write>(
+    builder: &mut Builder<W>,
+    status_output: &mut impl Write,
+    source: &Path,
+    display: &Path,
+    name: &Path,
+    state: &mut CreateState,
+    options: &CreateOptions,
+) -> UResult<()> {
+    let mut stack = vec![(
+        source.to_path_buf(),
+        display.to_path_buf(),
+        name.to_path_buf(),
+    )];
+    let mut top_device = None;
+
+    while let Some((source, display, name)) = stack.pop() {
+        let meta = stat(&source, options).map_err(|e| TarError::CannotAddFile {
+            path: display.clone(),
+            source: e,
+        })?;
+        let is_dir = meta.is_dir();
+        let device = device(&meta);
+        let top_device = *top_device.get_or_insert(device);
+        if options
+            .newer
+            .is_some_and(|newer| !is_dir && !newer.includes(&meta))
+        {
+            if options.verbose {
+                writeln!(
+                    io::stderr(),
+                    "tar: {}: file is unchanged; not dumped",
+                    display.display()
+                )
+                .map_err(TarError::Io)?;
+            }
+            continue;
+        }
+        let member = options.transform.apply_path(&name, TransformScope::Regular);
+
+        if options.verbose {
+            let shown = match &member {
+                Some(member) if options.show_transformed_names => member,
+                _ => &display,
+            };
             if is_dir {
-                format!("{}{}", path, path::MAIN_SEPARATOR)
+                writeln!(status_output, "{}{}", shown.display(), path::MAIN_SEPARATOR)
             } else {
-                path
+                writeln!(status_output, "{}", shown.display())
             }
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
-    writeln!(status_output, "{to_print}").map_err(TarError::Io)?;
+            .map_err(TarError::Io)?;
+        }
+
+        // Directories are read first, as dumpdir members list their contents
+        let mut children = Vec::new();
+        let mut dumpdir = None;
+        if is_dir && options.recursion {
+            if options.one_file_system && device != top_device {
+                writeln!(
+                    io::stderr(),
+                    "tar: {}: file is on a different filesystem; not dumped",
+                    display.display()
+                )
+                .map_err(TarError::Io)?;
+            } else {
+                children = fs::read_dir(&source)
+                    .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+                    .map_err(|e| TarError::CannotAddDirectory {
+                        path: display.clone(),
+                        source: e,
+                    })?;
+                sort_entries(&mut children, options.sort);
+                if let Some(incremental) = &mut state.incremental {
+                    let contents =
+                        incremental.scan_directory(&name, &meta, &mut children, |path| {
+                            stat(path, options)
+                        });
+                    dumpdir = Some(contents.to_bytes());
+                }
+            }
+        }
+
+        if let Some(member) = member {
+            let linked = link_key(&meta);
+            let data = match (&dumpdir, linked.and_then(|(key, _)| state.links.get(&key))) {
+                (Some(contents), _) => MemberData::Dumpdir(contents),
+                (None, Some(file)) if !options.hard_dereference => MemberData::HardLink(&file.name),
+                _ => MemberData::FromDisk,
+            };
+            append_member(builder, &source, &meta, &member, data, options).map_err(|e| {
+                if is_dir {
+                    TarError::CannotAddDirectory {
+                        path: display.clone(),
+                        source: e,
+                    }
+                } else {
+                    TarError::CannotAddFile {
+                        path: display.clone(),
+                        source: e,
+                    }
+                }
+            })?;
+            if options.remove_files {
+                state.archived.push(ArchivedFile {
+                    source: source.clone(),
+                    display: display.clone(),
+                    is_dir,
+                });
+            }
+            if let Some((key, nlink)) = linked {
+                state
+                    .links
+                    .entry(key)
+                    .and_modify(|file| file.missing = file.missing.saturating_sub(1))
+                    .or_insert(LinkedFile {
+                        name: stored_name(&member),
+                        missing: nlink - 1,
+                    });
+            }
+        }
+        // Reversed so that popping the stack visits them in sorted order
+        for child in children.into_iter().rev() {
+            let file_name = child.file_name();
+            stack.push((
+                child.path(),
+                display.join(&file_name),
+                name.join(&file_name),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// `name` as the archive stores it, without its `.` components, so that
+/// later links point at the member actually written.
+fn stored_name(name: &Path) -> PathBuf {
+    name.components()
+        .filter(|component| *component != CurDir)
+        .collect()
+}
+
+/// Metadata of `path`, following symlinks under `-h`.
+fn stat(path: &Path, options: &CreateOptions) -> io::Result<fs::Metadata> {
+    if options.dereference {
+        fs::metadata(path)
+    } else {
+        fs::symlink_metadata(path)
+    }
+}
+
+/// Sort the entries of a directory in place.
+fn sort_entries(entries: &mut [fs::DirEntry], order: SortOrder) {
+    match order {
+        SortOrder::None => {}
+        SortOrder::Name => entries.sort_by_key(fs::DirEntry::file_name),
+        SortOrder::Inode => entries.sort_by_key(inode),
+    }
+}
+
+#[cfg(unix)]
+fn inode(entry: &fs::DirEntry) -> u64 {
+    use std::os::unix::fs::DirEntryExt;
+
+    entry.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_entry: &fs::DirEntry) -> u64 {
+    0
+}
+
+/// The device a file lives on.
+#[cfg(unix)]
+fn device(meta: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_meta: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// The device and inode of a file that has other hard links, along with
+/// its link count.
+#[cfg(unix)]
+fn link_key(meta: &fs::Metadata) -> Option<((u64, u64), u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    (!meta.is_dir() && meta.nlink() > 1).then(|| ((meta.dev(), meta.ino()), meta.nlink()))
+}
+
+#[cfg(not(unix))]
+fn link_key(_meta: &fs::Metadata) -> Option<((u64, u64), u64)> {
+    None
+}
+
+/// What to store for a member besides the metadata of its file
+enum MemberData<'a> {
+    /// The file's contents, or link target for a symlink
+    FromDisk,
+    /// A hard link to this earlier member
+    HardLink(&'a Path),
+    /// The listing of an incremental dump's directory
+    Dumpdir(&'a [u8]),
+}
+
+/// Append the file at `source`, described by `meta`, as the member `name`.
+///
+/// The header is filled from `meta` as `Builder::append_path_with_name`
+/// would, and then any `--owner`, `--group`, `--mode` or `--mtime`
+/// overrides are applied on top.
+fn append_member<W: Write>(
+    builder: &mut Builder<W>,
+    source: &Path,
+    meta: &fs::Metadata,
+    name: &Path,
+    data: MemberData<'_>,
+    options: &CreateOptions,
+) -> io::Result<()> {
+    let mut header = Header::new_gnu();
+    header.set_metadata_in_mode(meta, HeaderMode::Complete);
+    set_special_fields(&mut header, meta)?;
+    options.overrides.apply(&mut header)?;
+    // Directories are named with a trailing `/`
+    let dir_name;
+    let name = if meta.is_dir() {
+        dir_name = name.join("");
+        &dir_name
+    } else {
+        name
+    };
+
+    if let MemberData::HardLink(target) = data {
+        header.set_entry_type(EntryType::Link);
+        header.set_size(0);
+        builder.append_link(&mut header, name, target)
+    } else if let MemberData::Dumpdir(contents) = data {
+        header.set_entry_type(EntryType::new(DUMPDIR));
+        header.set_size(contents.len() as u64);
+        builder.append_data(&mut header, name, contents)
+    } else if meta.file_type().is_symlink() {
+        let target = fs::read_link(source)?;
+        let target = options
+            .transform
+            .apply_path(&target, TransformScope::Symlink)
+            .unwrap_or(target);
+        builder.append_link(&mut header, name, target)
+    } else if meta.is_file() {
+        builder.append_data(&mut header, name, fs::File::open(source)?)
+    } else {
+        builder.append_data(&mut header, name, io::empty())
+    }
+}
+
+/// Record the device numbers of block and character devices, and reject
+/// sockets, which cannot be archived.
+#[cfg(unix)]
+fn set_special_fields(header: &mut Header, meta: &fs::Metadata) -> io::Result<()> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let file_type = meta.file_type();
+    if file_type.is_socket() {
+        return Err(io::Error::other("socket can not be archived"));
+    }
+    if file_type.is_block_device() || file_type.is_char_device() {
+        let dev = meta.rdev();
+        let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0x0000_0fff);
+        let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0x0000_00ff);
+        header.set_device_major(major as u32)?;
+        header.set_device_minor(minor as u32)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_special_fields(_header: &mut Header, _meta: &fs::Metadata) -> io::Result<()> {
     Ok(())
 }
 
@@ -122,30 +631,16 @@ fn get_normalized_path(path: &Path, allow_absolute: bool) -> Result<PathBuf, Tar
                 removed.display()
             )?;
         }
+        // Nothing is left of a bare root: it heads the archive as `./`
+        if normalized.as_os_str().is_empty() {
+            return Ok(PathBuf::from("."));
+        }
         Ok(normalized)
     } else {
         Ok(path.to_path_buf())
     }
 }
 
-fn get_tree(path: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
-    let mut paths = Vec::new();
-    let mut stack = VecDeque::new();
-    stack.push_back(path.to_path_buf());
-
-    while let Some(current) = stack.pop_back() {
-        paths.push(current.clone());
-        if current.is_dir() {
-            for entry in fs::read_dir(current)? {
-                let child = entry?.path();
-                stack.push_back(child);
-            }
-        }
-    }
-
-    Ok(paths)
-}
-
 fn normalize_path(path: &Path, allow_absolute: bool) -> Option<PathBuf> {
     if path.is_absolute() && !allow_absolute {
         Some(
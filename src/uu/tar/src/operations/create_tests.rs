@@ -30,10 +30,11 @@ fn test_create_archive_flush_failed() {
     let res = create_archive(
         output,
         status_output,
-        &[file_path.as_path()],
-        false,
-        false,
-        CompressionMode::None,
+        &[file_path.as_path().into()],
+        &CreateOptions {
+            compression: CompressionMode::None,
+            ..Default::default()
+        },
     );
     assert!(res.is_err());
 }
@@ -50,10 +51,11 @@ fn test_create_archive_gzip_flush_failed() {
     let res = create_archive(
         output,
         status_output,
-        &[file_path.as_path()],
-        false,
-        false,
-        CompressionMode::Gzip,
+        &[file_path.as_path().into()],
+        &CreateOptions {
+            compression: CompressionMode::Gzip,
+            ..Default::default()
+        },
     );
     assert!(res.is_err());
 }
@@ -67,10 +69,11 @@ fn test_create_archive_with_zstd() {
     create_archive(
         fs::File::create("archive.tar.zst").unwrap(),
         io::sink(),
-        &[Path::new("file.txt")],
-        false,
-        false,
-        CompressionMode::Zstd,
+        &[Path::new("file.txt").into()],
+        &CreateOptions {
+            compression: CompressionMode::Zstd,
+            ..Default::default()
+        },
     )
     .unwrap();
 
@@ -90,11 +93,155 @@ fn test_create_archive_missing_file_fails() {
     let err = create_archive(
         io::sink(),
         io::sink(),
-        &[missing_path.as_path()],
-        false,
-        false,
-        CompressionMode::Zstd,
+        &[missing_path.as_path().into()],
+        &CreateOptions {
+            compression: CompressionMode::Zstd,
+            ..Default::default()
+        },
     )
     .unwrap_err();
     assert!(err.to_string().contains("missing.txt"));
 }
+
+#[test]
+fn test_create_archive_resolves_operands_against_directory() {
+    let tempdir = tempdir().unwrap();
+    let build = tempdir.path().join("build");
+    fs::create_dir_all(build.join("bin")).unwrap();
+    fs::write(build.join("bin/tool"), "tool").unwrap();
+
+    let mut archive_bytes = Vec::new();
+    create_archive(
+        &mut archive_bytes,
+        io::sink(),
+        &[FileOperand {
+            directory: Some(&build),
+            path: Path::new("bin"),
+        }],
+        &CreateOptions {
+            compression: CompressionMode::None,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut archive = Archive::new(archive_bytes.as_slice());
+    let names: Vec<PathBuf> = archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().into_owned())
+        .collect();
+    assert_eq!(names, [PathBuf::from("bin"), PathBuf::from("bin/tool")]);
+}
+
+#[test]
+fn test_create_archive_missing_directory_fails() {
+    let tempdir = tempdir().unwrap();
+    let missing_dir = tempdir.path().join("missing");
+
+    let err = create_archive(
+        io::sink(),
+        io::sink(),
+        &[FileOperand {
+            directory: Some(&missing_dir),
+            path: Path::new("file.txt"),
+        }],
+        &CreateOptions {
+            compression: CompressionMode::None,
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Cannot chdir"));
+}
+
+#[test]
+fn test_create_archive_transforms_member_names() {
+    let tempdir = tempdir().unwrap();
+    let build = tempdir.path().join("build");
+    fs::create_dir_all(build.join("bin")).unwrap();
+    fs::write(build.join("bin/tool"), "tool").unwrap();
+
+    let mut transform = Transform::default();
+    transform.add_expressions("s,^bin,usr/bin,").unwrap();
+    let mut archive_bytes = Vec::new();
+    create_archive(
+        &mut archive_bytes,
+        io::sink(),
+        &[FileOperand {
+            directory: Some(&build),
+            path: Path::new("bin"),
+        }],
+        &CreateOptions {
+            compression: CompressionMode::None,
+            transform,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut archive = Archive::new(archive_bytes.as_slice());
+    let names: Vec<PathBuf> = archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().into_owned())
+        .collect();
+    assert_eq!(
+        names,
+        [PathBuf::from("usr/bin"), PathBuf::from("usr/bin/tool")]
+    );
+}
+
+#[test]
+fn test_create_archive_sorts_by_name() {
+    let tempdir = tempdir().unwrap();
+    let build = tempdir.path().join("build");
+    fs::create_dir_all(&build).unwrap();
+    for name in ["c", "a", "b"] {
+        fs::write(build.join(name), name).unwrap();
+    }
+
+    let mut archive_bytes = Vec::new();
+    create_archive(
+        &mut archive_bytes,
+        io::sink(),
+        &[FileOperand {
+            directory: Some(tempdir.path()),
+            path: Path::new("build"),
+        }],
+        &CreateOptions {
+            sort: SortOrder::Name,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let mut archive = Archive::new(archive_bytes.as_slice());
+    let names: Vec<PathBuf> = archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().into_owned())
+        .collect();
+    assert_eq!(
+        names,
+        ["build", "build/a", "build/b", "build/c"].map(PathBuf::from)
+    );
+}
+
+#[test]
+fn test_normalized_root_is_current_directory() {
+    for root in ["/", "//", "/."] {
+        assert_eq!(
+            get_normalized_path(Path::new(root), false).unwrap(),
+            PathBuf::from(".")
+        );
+    }
+    assert_eq!(
+        get_normalized_path(Path::new("/etc"), false).unwrap(),
+        PathBuf::from("etc")
+    );
+    assert_eq!(
+        get_normalized_path(Path::new("/"), true).unwrap(),
+        PathBuf::from("/")
+    );
+}
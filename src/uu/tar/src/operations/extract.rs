@@ -5,19 +5,140 @@
 
 use crate::compression::open_archive_reader;
 use crate::errors::TarError;
+use crate::operations::incremental::{purge_directory, Dumpdir, DUMPDIR};
+use crate::operations::label::{check_first_label, VOLUME_LABEL};
+use crate::operations::scanner::{report_notices, BlockScanner};
+use crate::operations::{check_directory, strip_path_components};
+use crate::progress::{Direction, Meter, ProgressOptions};
+use crate::record::DEFAULT_BLOCKING_FACTOR;
+use crate::transform::{Transform, TransformScope};
 use crate::CompressionMode;
+use std::fs;
 use std::io::Read;
 use std::io::{self, BufWriter, Write};
-use std::path::Path;
-use tar::Archive;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, UNIX_EPOCH};
+use tar::{Archive, Entry, EntryType};
 use uucore::error::UResult;
 
+/// How to treat members whose destination already exists
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OldFiles {
+    /// Replace existing files, keeping existing directories
+    #[default]
+    Replace,
+    /// Write into existing regular files rather than replacing them
+    /// (`--overwrite`)
+    Overwrite,
+    /// Remove existing files before extracting over them (`-U`)
+    UnlinkFirst,
+    /// Refuse to replace existing files and report an error (`-k`)
+    KeepOld,
+    /// Silently skip existing files (`--skip-old-files`)
+    SkipOld,
+    /// Skip existing files that are as new as the archive member
+    /// (`--keep-newer-files`)
+    KeepNewer,
+}
+
+/// Options controlling archive extraction
+#[derive(Debug)]
+pub struct ExtractOptions {
+    /// Directory to extract into (`-C`); the current directory if unset
+    pub directory: Option<PathBuf>,
+    /// Print each member as it is extracted (`-v`)
+    pub verbose: bool,
+    /// Compression of the archive stream
+    pub compression: CompressionMode,
+    /// Leading path components to remove from member names and hard link
+    /// targets (`--strip-components`)
+    pub strip_components: usize,
+    /// Rewrite member names and link targets (`--transform`)
+    pub transform: Transform,
+    /// Print transformed rather than stored names under `-v`
+    pub show_transformed_names: bool,
+    /// Policy for members that already exist on disk
+    pub old_files: OldFiles,
+    /// Update the metadata of existing directories (`--overwrite-dir`)
+    pub overwrite_dir: bool,
+    /// Remove existing directory hierarchies that are in the way
+    /// (`--recursive-unlink`)
+    pub recursive_unlink: bool,
+    /// Write the contents of regular files to standard output instead of
+    /// creating them (`-O`); verbose output then goes to standard error
+    pub to_stdout: bool,
+    /// Pipe the contents of regular files to this shell command instead of
+    /// creating them (`--to-command`)
+    pub to_command: Option<String>,
+    /// Carry on when a `--to-command` child exits unsuccessfully
+    /// (`--ignore-command-error`)
+    pub ignore_command_error: bool,
+    /// Restore every mode bit, including setuid, setgid and sticky, without
+    /// applying the umask (`-p`)
+    pub same_permissions: bool,
+    /// Give extracted files the owner and group recorded in the archive
+    /// (`--same-owner`)
+    pub same_owner: bool,
+    /// Use the recorded uid and gid even when the user and group names are
+    /// known here (`--numeric-owner`)
+    pub numeric_owner: bool,
+    /// Leave modification times at the time of extraction (`-m`)
+    pub touch: bool,
+    /// Restore directory metadata only after the whole archive has been
+    /// extracted, rather than once the directory's members have been seen
+    /// (`--delay-directory-restore`)
+    pub delay_directory_restore: bool,
+    /// Remove files that dumpdir members of an incremental archive no
+    /// longer list (`-g`)
+    pub listed_incremental: bool,
+    /// Wildcard pattern the volume label must match (`-V`)
+    pub label: Option<String>,
+    /// Read on past zero blocks, such as the end of each archive in a
+    /// concatenation (`-i`)
+    pub ignore_zeros: bool,
+    /// Blocks per record, reported to `--to-command` (`-b`)
+    pub blocking_factor: usize,
+    /// Totals and checkpoints to report (`--totals`, `--checkpoint`)
+    pub progress: ProgressOptions,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            verbose: false,
+            compression: CompressionMode::Auto,
+            strip_components: 0,
+            transform: Transform::default(),
+            show_transformed_names: false,
+            old_files: OldFiles::Replace,
+            overwrite_dir: true,
+            recursive_unlink: false,
+            to_stdout: false,
+            to_command: None,
+            ignore_command_error: false,
+            same_permissions: false,
+            same_owner: false,
+            numeric_owner: false,
+            touch: false,
+            delay_directory_restore: false,
+            listed_incremental: false,
+            label: None,
+            ignore_zeros: false,
+            blocking_factor: DEFAULT_BLOCKING_FACTOR,
+            progress: ProgressOptions::default(),
+        }
+    }
+}
+
 /// Extract files from a tar archive
 ///
 /// # Arguments
 ///
+/// * `input` - Reader producing the (possibly compressed) archive
 /// * `archive_path` - Path to the tar archive to extract
-/// * `verbose` - Whether to print verbose output during extraction
+/// * `options` - Flags controlling where and how members are extracted
 ///
 /// # Errors
 ///
@@ -25,24 +146,58 @@ use uucore::error::UResult;
 /// - The archive file cannot be opened
 /// - The archive format is invalid
 /// - Files cannot be extracted due to I/O or permission errors
+/// - Existing files were kept under `-k`; the remaining members are still
+///   extracted first
 pub fn extract_archive(
     input: impl Read,
     archive_path: &Path,
-    verbose: bool,
-    compression: CompressionMode,
+    options: &ExtractOptions,
 ) -> UResult<()> {
-    let reader = open_archive_reader(input, compression)?;
-    let mut archive = Archive::new(reader);
+    let directory = options.directory.as_deref().unwrap_or(Path::new("."));
+    check_directory(directory)?;
+
+    let mut input = Meter::new(input, &options.progress, Direction::Read);
+    let scanner = BlockScanner::new(open_archive_reader(&mut input, options.compression)?);
+    let notices = scanner.notices();
+    let mut archive = Archive::new(scanner);
+    archive.set_ignore_zeros(options.ignore_zeros);
     let mut out = BufWriter::new(io::stdout().lock());
+    // With -O stdout carries file data, so member names go to stderr
+    let mut err = io::stderr().lock();
+    let mut kept_old_files = false;
+    let mask = if options.same_permissions {
+        0
+    } else {
+        current_umask()
+    };
+    let mut pending_dirs: Vec<PendingDir> = Vec::new();
 
-    // Extract to current directory
-    if verbose {
-        writeln!(out, "Extracting archive: {}", archive_path.display()).map_err(TarError::Io)?;
+    if options.verbose {
+        let status: &mut dyn Write = if options.to_stdout {
+            &mut err
+        } else {
+            &mut out
+        };
+        writeln!(status, "Extracting archive: {}", archive_path.display()).map_err(TarError::Io)?;
+    }
+
+    let mut entries = archive
+        .entries()
+        .map_err(TarError::CannotReadEntries)?
+        .peekable();
+    if let Some(pattern) = &options.label {
+        check_first_label(pattern, &mut entries)?;
     }
 
     // Iterate through entries for verbose output and error handling
-    for entry_result in archive.entries().map_err(TarError::CannotReadEntries)? {
+    let mut damaged = false;
+    for entry_result in entries {
         let mut entry = entry_result.map_err(TarError::CannotReadEntry)?;
+        damaged |= report_notices(&notices, &mut out, options.verbose)?;
+        // A volume label names the archive, not a file to create
+        if entry.header().entry_type().as_byte() == VOLUME_LABEL {
+            continue;
+        }
 
         // Get the path before unpacking (clone it so we can use it after borrowing entry mutably)
         let path = entry
@@ -50,21 +205,615 @@ pub fn extract_archive(
             .map_err(TarError::CannotReadEntryPath)?
             .to_path_buf();
 
-        if verbose {
-            writeln!(out, "{}", path.display()).map_err(TarError::Io)?;
+        // Members whose whole name is stripped away are skipped
+        let Some(stripped) = strip_path_components(&path, options.strip_components) else {
+            continue;
+        };
+        let Some(name) = options
+            .transform
+            .apply_path(&stripped, TransformScope::Regular)
+        else {
+            continue;
+        };
+
+        let link_target = match link_target(&entry, options)? {
+            LinkTarget::Unchanged => None,
+            LinkTarget::Changed(target) => Some(target),
+            LinkTarget::Skip => continue,
+        };
+
+        if options.verbose {
+            let shown = if options.show_transformed_names {
+                &name
+            } else {
+                &stripped
+            };
+            let status: &mut dyn Write = if options.to_stdout {
+                &mut err
+            } else {
+                &mut out
+            };
+            writeln!(status, "{}", shown.display()).map_err(TarError::Io)?;
+        }
+
+        if options.to_stdout {
+            if is_regular_file(entry.header().entry_type()) {
+                io::copy(&mut entry, &mut out).map_err(TarError::Io)?;
+            }
+            continue;
+        }
+
+        if let Some(command) = &options.to_command {
+            if is_regular_file(entry.header().entry_type()) {
+                // The child shares our stdout; keep verbose output in order
+                out.flush().map_err(TarError::Io)?;
+                let member = CommandMember {
+                    name: &name,
+                    realname: &path,
+                    archive_path,
+                    directory,
+                    blocking_factor: options.blocking_factor,
+                };
+                pipe_to_command(command, &mut entry, &member, options.ignore_command_error)?;
+            }
+            continue;
+        }
+
+        if !options.delay_directory_restore {
+            if let Some(dst) = resolve_inside(directory, &name) {
+                restore_directories(&mut pending_dirs, |dir| !dst.starts_with(dir))?;
+            }
         }
 
         // Unpack the entry
-        entry.unpack_in(".").map_err(|e| TarError::CannotExtract {
-            path: path.clone(),
+        let unpacked = unpack_entry(
+            &mut entry,
+            directory,
+            &name,
+            link_target.as_deref(),
+            options,
+            mask,
+        )
+        .map_err(|e| TarError::CannotExtract {
+            path: name.clone(),
             source: e,
         })?;
+        match unpacked {
+            Unpacked::Done | Unpacked::Skipped => {}
+            Unpacked::Directory(dir) => pending_dirs.push(dir),
+            Unpacked::Exists => {
+                out.flush().map_err(TarError::Io)?;
+                writeln!(
+                    io::stderr(),
+                    "tar: {}: Cannot open: File exists",
+                    name.display()
+                )?;
+                kept_old_files = true;
+            }
+            Unpacked::NotNewer => {
+                out.flush().map_err(TarError::Io)?;
+                writeln!(
+                    io::stderr(),
+                    "tar: Current '{}' is newer or same age",
+                    name.display()
+                )?;
+            }
+        }
     }
 
+    restore_directories(&mut pending_dirs, |_| true)?;
+    damaged |= report_notices(&notices, &mut out, options.verbose)?;
     out.flush().map_err(TarError::Io)?;
+    drop(archive);
+    input.finish()?;
+    if kept_old_files || damaged {
+        return Err(TarError::PreviousErrors.into());
+    }
+    Ok(())
+}
+
+/// Where a member piped to `--to-command` came from
+struct CommandMember<'a> {
+    /// Name after `--strip-components` and `--transform`
+    name: &'a Path,
+    /// Name as stored in the archive
+    realname: &'a Path,
+    archive_path: &'a Path,
+    /// Working directory for the command (`-C`)
+    directory: &'a Path,
+    /// Blocks per record of the archive (`-b`)
+    blocking_factor: usize,
+}
+
+/// Feed the contents of `entry` to `command`, run through the shell with
+/// the member's metadata exported in `TAR_*` environment variables.
+fn pipe_to_command<R: Read>(
+    command: &str,
+    entry: &mut Entry<'_, R>,
+    member: &CommandMember<'_>,
+    ignore_errors: bool,
+) -> Result<(), TarError> {
+    let header = entry.header();
+    let format = if header.as_gnu().is_some() {
+        "gnu"
+    } else if header.as_ustar().is_some() {
+        "ustar"
+    } else {
+        "v7"
+    };
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .current_dir(member.directory)
+        .stdin(Stdio::piped())
+        .env("TAR_VERSION", env!("CARGO_PKG_VERSION"))
+        .env("TAR_ARCHIVE", member.archive_path)
+        .env("TAR_VOLUME", "1")
+        .env("TAR_BLOCKING_FACTOR", member.blocking_factor.to_string())
+        .env("TAR_FORMAT", format)
+        .env("TAR_FILETYPE", "f")
+        .env("TAR_FILENAME", member.name)
+        .env("TAR_REALNAME", member.realname)
+        .env("TAR_SIZE", entry.size().to_string())
+        .env(
+            "TAR_MODE",
+            format!("0{:o}", header.mode().unwrap_or(0) & 0o7777),
+        )
+        .env("TAR_UID", header.uid().unwrap_or(0).to_string())
+        .env("TAR_GID", header.gid().unwrap_or(0).to_string())
+        .env("TAR_UNAME", header.username().ok().flatten().unwrap_or(""))
+        .env("TAR_GNAME", header.groupname().ok().flatten().unwrap_or(""))
+        .env("TAR_MTIME", header.mtime().unwrap_or(0).to_string());
+    // GNU headers may also record access and change times
+    if let Some(gnu) = header.as_gnu() {
+        if let Some(atime) = gnu.atime().ok().filter(|&t| t != 0) {
+            cmd.env("TAR_ATIME", atime.to_string());
+        }
+        if let Some(ctime) = gnu.ctime().ok().filter(|&t| t != 0) {
+            cmd.env("TAR_CTIME", ctime.to_string());
+        }
+    }
+
+    let mut child = cmd.spawn().map_err(|e| TarError::CannotRunCommand {
+        command: command.to_string(),
+        source: e,
+    })?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let copied = match io::copy(entry, &mut stdin) {
+        // The command need not read all of its input
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(0),
+        result => result,
+    };
+    drop(stdin);
+    let status = child.wait().map_err(TarError::Io)?;
+    copied.map_err(TarError::Io)?;
+
+    if status.success() || ignore_errors {
+        return Ok(());
+    }
+    let pid = child.id();
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return Err(TarError::ChildKilled { pid, signal });
+    }
+    Err(TarError::ChildFailed {
+        pid,
+        code: status.code().unwrap_or(1),
+    })
+}
+
+enum LinkTarget {
+    /// Not a link, or a symlink whose target is kept as stored
+    Unchanged,
+    /// Create the link pointing at this target instead
+    Changed(PathBuf),
+    /// The target was stripped or transformed away; skip the member
+    Skip,
+}
+
+/// Work out the link target to use for `entry`.
+///
+/// Hard link targets name other members, so they get the same
+/// `--strip-components` treatment as member names; both kinds of link are
+/// subject to `--transform` under their own scope flags.
+fn link_target<R: Read>(
+    entry: &Entry<'_, R>,
+    options: &ExtractOptions,
+) -> Result<LinkTarget, TarError> {
+    let entry_type = entry.header().entry_type();
+    if !entry_type.is_hard_link() && !entry_type.is_symlink() {
+        return Ok(LinkTarget::Unchanged);
+    }
+    let target = entry
+        .link_name()
+        .map_err(TarError::CannotReadEntryPath)?
+        .unwrap_or_default()
+        .into_owned();
+
+    if entry_type.is_hard_link() {
+        let target = strip_path_components(&target, options.strip_components)
+            .and_then(|t| options.transform.apply_path(&t, TransformScope::Hardlink));
+        return Ok(target.map_or(LinkTarget::Skip, LinkTarget::Changed));
+    }
+
+    match options
+        .transform
+        .apply_path(&target, TransformScope::Symlink)
+    {
+        Some(new_target) if new_target != target => Ok(LinkTarget::Changed(new_target)),
+        Some(_) => Ok(LinkTarget::Unchanged),
+        None => Ok(LinkTarget::Skip),
+    }
+}
+
+/// Result of unpacking a single member
+enum Unpacked {
+    /// The member was written to disk
+    Done,
+    /// The directory exists; its metadata is still to be restored
+    Directory(PendingDir),
+    /// The member was deliberately not written
+    Skipped,
+    /// The destination exists and `-k` forbids replacing it
+    Exists,
+    /// The destination is at least as new as the member
+    /// (`--keep-newer-files`)
+    NotNewer,
+}
+
+/// Unpack `entry` as `name` inside `directory`.
+///
+/// This mirrors `Entry::unpack_in`, but lets the caller choose the member
+/// name (and link target) instead of taking them from the header.
+/// Names containing `..` are skipped, and the parent directory must resolve
+/// inside `directory` so that symlinks in the archive cannot redirect the
+/// write elsewhere.  Whatever already exists at the destination is dealt
+/// with according to `options.old_files`.
+///
+/// Directory metadata is not applied here, since creating the directory's
+/// members would disturb its modification time (and a read-only mode would
+/// prevent creating them at all); it is handed back to the caller instead.
+fn unpack_entry<R: Read>(
+    entry: &mut Entry<'_, R>,
+    directory: &Path,
+    name: &Path,
+    link_target: Option<&Path>,
+    options: &ExtractOptions,
+    mask: u32,
+) -> io::Result<Unpacked> {
+    let Some(dst) = resolve_inside(directory, name) else {
+        return Ok(Unpacked::Skipped);
+    };
+    let is_dumpdir = entry.header().entry_type().as_byte() == DUMPDIR;
+    if is_dumpdir && options.listed_incremental {
+        purge_dumpdir(entry, &dst)?;
+    }
+    // Entries such as "./" name the extraction directory itself
+    if dst == directory {
+        return Ok(Unpacked::Skipped);
+    }
+
+    let parent = dst.parent().unwrap_or(directory);
+    create_parent_dirs(directory, parent)?;
+
+    let header = entry.header();
+    let entry_type = header.entry_type();
+    let is_dir = entry_type.is_dir() || is_dumpdir;
+    // Like `Entry::unpack`, leave out whatever the header doesn't record
+    let mode = header
+        .mode()
+        .ok()
+        .map(|mode| file_mode(mode, options.same_permissions, mask));
+    let mtime = header.mtime().ok().filter(|_| !options.touch);
+    let owner = member_owner(header, options);
+
+    let mut in_place = false;
+    if let Ok(existing) = fs::symlink_metadata(&dst) {
+        match options.old_files {
+            OldFiles::KeepOld | OldFiles::SkipOld if is_dir && existing.is_dir() => {
+                return Ok(Unpacked::Skipped);
+            }
+            OldFiles::KeepOld => return Ok(Unpacked::Exists),
+            OldFiles::SkipOld => return Ok(Unpacked::Skipped),
+            OldFiles::KeepNewer if !existing.is_dir() && !is_newer(entry, &existing) => {
+                return Ok(Unpacked::NotNewer);
+            }
+            _ => {}
+        }
+
+        if existing.is_dir() {
+            if is_dir {
+                if options.old_files == OldFiles::UnlinkFirst && options.recursive_unlink {
+                    fs::remove_dir_all(&dst)?;
+                } else if !options.overwrite_dir {
+                    return Ok(Unpacked::Skipped);
+                }
+            } else if options.recursive_unlink {
+                fs::remove_dir_all(&dst)?;
+            } else {
+                fs::remove_dir(&dst)?;
+            }
+        } else if options.old_files == OldFiles::Overwrite
+            && existing.is_file()
+            && is_regular_file(entry_type)
+        {
+            in_place = true;
+        } else {
+            fs::remove_file(&dst)?;
+        }
+    }
+
+    if is_dir {
+        match fs::create_dir(&dst) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && dst.is_dir() => {}
+            result => result?,
+        }
+        return Ok(Unpacked::Directory(PendingDir {
+            path: dst,
+            mode,
+            mtime,
+            owner,
+        }));
+    }
+
+    match link_target {
+        None if in_place => overwrite_in_place(entry, &dst, mode, mtime)?,
+        None => {
+            entry.set_mask(mask);
+            entry.set_preserve_permissions(options.same_permissions);
+            entry.set_preserve_mtime(!options.touch);
+            entry.unpack(&dst)?;
+        }
+        Some(target) if entry_type.is_symlink() => symlink(target, &dst)?,
+        Some(target) => {
+            let Some(src) = resolve_inside(directory, target) else {
+                return Ok(Unpacked::Skipped);
+            };
+            validate_inside(directory, &src)?;
+            // Hard links share the metadata of the file they link to
+            fs::hard_link(&src, &dst)?;
+            return Ok(Unpacked::Done);
+        }
+    }
+
+    if let Some(owner) = owner {
+        let mode = mode.filter(|_| !entry_type.is_symlink());
+        set_owner(&dst, owner, mode)?;
+    }
+    Ok(Unpacked::Done)
+}
+
+/// Remove whatever the dumpdir `entry` doesn't list from the existing
+/// directory `dst`, as it was deleted before the incremental dump was made.
+fn purge_dumpdir<R: Read>(entry: &mut Entry<'_, R>, dst: &Path) -> io::Result<()> {
+    // A symlink in its place is replaced rather than followed
+    if !fs::symlink_metadata(dst).is_ok_and(|meta| meta.is_dir()) {
+        return Ok(());
+    }
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data)?;
+    let contents = Dumpdir::parse(&data)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed dumpdir"))?;
+    purge_directory(dst, &contents)
+}
+
+/// Directory metadata to apply once the directory's members are extracted
+struct PendingDir {
+    path: PathBuf,
+    mode: Option<u32>,
+    mtime: Option<u64>,
+    owner: Option<Owner>,
+}
+
+/// The uid and gid to give an extracted member; `None` leaves that id alone
+type Owner = (Option<u32>, Option<u32>);
+
+/// Restore the metadata of pending directories, innermost first, stopping
+/// at the first one for which `done` returns false.
+fn restore_directories(
+    pending: &mut Vec<PendingDir>,
+    done: impl Fn(&Path) -> bool,
+) -> Result<(), TarError> {
+    while let Some(dir) = pending.pop_if(|dir| done(&dir.path)) {
+        restore_directory(&dir).map_err(|e| TarError::CannotExtract {
+            path: dir.path,
+            source: e,
+        })?;
+    }
+    Ok(())
+}
+
+fn restore_directory(dir: &PendingDir) -> io::Result<()> {
+    if let Some(owner) = dir.owner {
+        set_owner(&dir.path, owner, None)?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Some(mtime) = dir.mtime {
+            fs::File::open(&dir.path)?.set_modified(UNIX_EPOCH + Duration::from_secs(mtime))?;
+        }
+        // Last, since the new mode may forbid the steps above
+        if let Some(mode) = dir.mode {
+            fs::set_permissions(&dir.path, fs::Permissions::from_mode(mode))?;
+        }
+    }
+    Ok(())
+}
+
+/// The permission bits to give an extracted member, as `-p` and the umask
+/// dictate.
+fn file_mode(mode: u32, same_permissions: bool, mask: u32) -> u32 {
+    if same_permissions {
+        mode & 0o7777
+    } else {
+        mode & 0o777 & !mask
+    }
+}
+
+#[cfg(unix)]
+fn current_umask() -> u32 {
+    uucore::mode::get_umask()
+}
+
+#[cfg(not(unix))]
+fn current_umask() -> u32 {
+    0
+}
+
+/// The owner to give an extracted member under `--same-owner`.
+///
+/// The user and group names recorded in the archive are preferred over the
+/// numeric ids, unless `--numeric-owner` is given or the name is not known
+/// on this system.
+#[cfg(unix)]
+fn member_owner(header: &tar::Header, options: &ExtractOptions) -> Option<Owner> {
+    use uucore::entries::{grp2gid, usr2uid};
+
+    if !options.same_owner {
+        return None;
+    }
+    let by_name = |name: Option<&str>, lookup: fn(&str) -> io::Result<u32>| {
+        name.filter(|_| !options.numeric_owner)
+            .and_then(|name| lookup(name).ok())
+    };
+    let uid = by_name(header.username().ok().flatten(), usr2uid)
+        .or_else(|| header.uid().ok().map(|uid| uid as u32));
+    let gid = by_name(header.groupname().ok().flatten(), grp2gid)
+        .or_else(|| header.gid().ok().map(|gid| gid as u32));
+    Some((uid, gid))
+}
+
+#[cfg(not(unix))]
+fn member_owner(_header: &tar::Header, _options: &ExtractOptions) -> Option<Owner> {
+    None
+}
+
+/// Change the owner of `path` without following symlinks, then restore
+/// `mode`, since changing the owner clears the setuid and setgid bits.
+#[cfg(unix)]
+fn set_owner(path: &Path, (uid, gid): Owner, mode: Option<u32>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::os::unix::fs::lchown(path, uid, gid)?;
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_owner(_path: &Path, _owner: Owner, _mode: Option<u32>) -> io::Result<()> {
+    Ok(())
+}
+
+/// Whether members of this type carry file contents
+fn is_regular_file(entry_type: EntryType) -> bool {
+    matches!(
+        entry_type,
+        EntryType::Regular | EntryType::Continuous | EntryType::GNUSparse
+    )
+}
+
+/// Whether the member's modification time is later than that of the file
+/// already on disk.
+fn is_newer<R: Read>(entry: &Entry<'_, R>, existing: &fs::Metadata) -> bool {
+    let Ok(mtime) = entry.header().mtime() else {
+        return false;
+    };
+    existing.modified().map_or(true, |modified| {
+        UNIX_EPOCH + Duration::from_secs(mtime) > modified
+    })
+}
+
+/// Write a regular file member over an existing regular file, keeping the
+/// file (and any other hard links to it) rather than replacing it.
+fn overwrite_in_place<R: Read>(
+    entry: &mut Entry<'_, R>,
+    dst: &Path,
+    mode: Option<u32>,
+    mtime: Option<u64>,
+) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(dst)?;
+    io::copy(entry, &mut file)?;
+
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    if let Some(mtime) = mtime {
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime))?;
+    }
     Ok(())
 }
 
+#[cfg(unix)]
+fn symlink(target: &Path, dst: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, dst)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, dst: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, dst)
+}
+
+/// Join the normal components of `name` onto `directory`, ignoring leading
+/// `/` and `.`.  Returns `None` if `name` contains `..`.
+fn resolve_inside(directory: &Path, name: &Path) -> Option<PathBuf> {
+    let mut dst = directory.to_path_buf();
+    for component in name.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => return None,
+            Component::Normal(part) => dst.push(part),
+        }
+    }
+    Some(dst)
+}
+
+/// Create `parent` and any missing ancestors, checking that the deepest
+/// existing ancestor lies inside `directory` before creating anything.
+fn create_parent_dirs(directory: &Path, parent: &Path) -> io::Result<()> {
+    let mut missing = Vec::new();
+    let mut ancestor = parent;
+    while ancestor.symlink_metadata().is_err() {
+        missing.push(ancestor);
+        match ancestor.parent() {
+            Some(p) => ancestor = p,
+            None => break,
+        }
+    }
+    validate_inside(directory, ancestor)?;
+    for dir in missing.into_iter().rev() {
+        match fs::create_dir(dir) {
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+            _ => {}
+        }
+    }
+    validate_inside(directory, parent)
+}
+
+fn validate_inside(directory: &Path, path: &Path) -> io::Result<()> {
+    if path.canonicalize()?.starts_with(directory.canonicalize()?) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "trying to unpack outside of destination path: {}",
+                directory.display()
+            ),
+        ))
+    }
+}
+
 #[cfg(test)]
 #[path = "extract_tests.rs"]
 mod tests;
@@ -6,32 +6,46 @@
 use super::*;
 use crate::CompressionMode;
 use std::fs;
+use std::path::PathBuf;
 use tar::Builder;
 use tempfile::tempdir;
 
-#[test]
-fn test_extract_archive_with_zstd() {
-    let tempdir = tempdir().unwrap();
-    let archive_path = tempdir.path().join("archive.tar.zst");
-
+fn single_file_tar(name: &str, contents: &str) -> Vec<u8> {
     let mut tar_bytes = Vec::new();
     {
         let mut builder = Builder::new(&mut tar_bytes);
         let mut header = tar::Header::new_gnu();
         header.set_mode(0o644);
-        header.set_size("hello".len() as u64);
+        header.set_size(contents.len() as u64);
         header.set_cksum();
         builder
-            .append_data(&mut header, "extracted.txt", std::io::Cursor::new("hello"))
+            .append_data(&mut header, name, std::io::Cursor::new(contents))
             .unwrap();
         builder.finish().unwrap();
     }
+    tar_bytes
+}
+
+#[test]
+fn test_extract_archive_with_zstd() {
+    let tempdir = tempdir().unwrap();
+    let archive_path = tempdir.path().join("archive.tar.zst");
+
+    let tar_bytes = single_file_tar("extracted.txt", "hello");
     let compressed = zstd::stream::encode_all(std::io::Cursor::new(tar_bytes), 0).unwrap();
     fs::write(&archive_path, compressed).unwrap();
 
     let _guard = crate::operations::TestDirGuard::enter(tempdir.path());
     let input = fs::File::open(&archive_path).unwrap();
-    let result = extract_archive(input, &archive_path, true, CompressionMode::Zstd);
+    let result = extract_archive(
+        input,
+        &archive_path,
+        &ExtractOptions {
+            verbose: true,
+            compression: CompressionMode::Zstd,
+            ..Default::default()
+        },
+    );
 
     result.unwrap();
     assert_eq!(
@@ -39,3 +53,307 @@ fn test_extract_archive_with_zstd() {
         "hello"
     );
 }
+
+#[test]
+fn test_extract_archive_into_directory() {
+    let tempdir = tempdir().unwrap();
+    let target = tempdir.path().join("target");
+    fs::create_dir(&target).unwrap();
+
+    let tar_bytes = single_file_tar("extracted.txt", "hello");
+    extract_archive(
+        tar_bytes.as_slice(),
+        Path::new("archive.tar"),
+        &ExtractOptions {
+            directory: Some(target.to_path_buf()),
+            compression: CompressionMode::None,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(target.join("extracted.txt")).unwrap(),
+        "hello"
+    );
+}
+
+#[test]
+fn test_extract_archive_into_missing_directory_fails() {
+    let tempdir = tempdir().unwrap();
+    let missing = tempdir.path().join("missing");
+
+    let tar_bytes = single_file_tar("extracted.txt", "hello");
+    let err = extract_archive(
+        tar_bytes.as_slice(),
+        Path::new("archive.tar"),
+        &ExtractOptions {
+            directory: Some(missing.to_path_buf()),
+            compression: CompressionMode::None,
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("Cannot chdir"));
+    assert!(!missing.exists());
+}
+
+#[test]
+fn test_extract_archive_strip_components() {
+    let tempdir = tempdir().unwrap();
+
+    let mut tar_bytes = Vec::new();
+    {
+        let mut builder = Builder::new(&mut tar_bytes);
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "project-1.2.3/", std::io::empty())
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size("hello".len() as u64);
+        builder
+            .append_data(
+                &mut header,
+                "project-1.2.3/src/lib.rs",
+                std::io::Cursor::new("hello"),
+            )
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        builder
+            .append_link(
+                &mut header,
+                "project-1.2.3/src/link.rs",
+                "project-1.2.3/src/lib.rs",
+            )
+            .unwrap();
+        builder.finish().unwrap();
+    }
+
+    extract_archive(
+        tar_bytes.as_slice(),
+        Path::new("archive.tar"),
+        &ExtractOptions {
+            directory: Some(tempdir.path().to_path_buf()),
+            compression: CompressionMode::None,
+            strip_components: 1,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(!tempdir.path().join("project-1.2.3").exists());
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("src/lib.rs")).unwrap(),
+        "hello"
+    );
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("src/link.rs")).unwrap(),
+        "hello"
+    );
+}
+
+#[test]
+fn test_extract_archive_skips_parent_dir_members() {
+    let tempdir = tempdir().unwrap();
+    let target = tempdir.path().join("target");
+    fs::create_dir(&target).unwrap();
+
+    let mut tar_bytes = Vec::new();
+    {
+        let mut builder = Builder::new(&mut tar_bytes);
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size("evil".len() as u64);
+        // `set_path` refuses `..`, so write the raw name field instead
+        header.as_old_mut().name[..11].copy_from_slice(b"../evil.txt");
+        header.set_cksum();
+        builder
+            .append(&header, std::io::Cursor::new("evil"))
+            .unwrap();
+        builder.finish().unwrap();
+    }
+
+    extract_archive(
+        tar_bytes.as_slice(),
+        Path::new("archive.tar"),
+        &ExtractOptions {
+            directory: Some(target.to_path_buf()),
+            compression: CompressionMode::None,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(!tempdir.path().join("evil.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_archive_transform_scopes() {
+    let tempdir = tempdir().unwrap();
+
+    let mut tar_bytes = Vec::new();
+    {
+        let mut builder = Builder::new(&mut tar_bytes);
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size("hello".len() as u64);
+        builder
+            .append_data(&mut header, "old/data.txt", std::io::Cursor::new("hello"))
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "old/symlink", "../old/data.txt")
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "old/hardlink", "old/data.txt")
+            .unwrap();
+        builder.finish().unwrap();
+    }
+
+    let mut transform = Transform::default();
+    transform.add_expressions("s,old,new,").unwrap();
+    extract_archive(
+        tar_bytes.as_slice(),
+        Path::new("archive.tar"),
+        &ExtractOptions {
+            directory: Some(tempdir.path().to_path_buf()),
+            compression: CompressionMode::None,
+            transform,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let new = tempdir.path().join("new");
+    assert!(!tempdir.path().join("old").exists());
+    assert_eq!(fs::read_to_string(new.join("data.txt")).unwrap(), "hello");
+    assert_eq!(
+        fs::read_link(new.join("symlink")).unwrap(),
+        Path::new("../new/data.txt")
+    );
+    assert_eq!(fs::read_to_string(new.join("symlink")).unwrap(), "hello");
+    assert_eq!(fs::read_to_string(new.join("hardlink")).unwrap(), "hello");
+}
+
+#[test]
+fn test_extract_archive_keep_old_files_extracts_the_rest() {
+    let tempdir = tempdir().unwrap();
+    fs::write(tempdir.path().join("existing.txt"), "kept").unwrap();
+
+    let mut tar_bytes = Vec::new();
+    {
+        let mut builder = Builder::new(&mut tar_bytes);
+        for name in ["existing.txt", "new.txt"] {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(0o644);
+            header.set_size("archived".len() as u64);
+            builder
+                .append_data(&mut header, name, std::io::Cursor::new("archived"))
+                .unwrap();
+        }
+        builder.finish().unwrap();
+    }
+
+    let err = extract_archive(
+        tar_bytes.as_slice(),
+        Path::new("archive.tar"),
+        &ExtractOptions {
+            directory: Some(tempdir.path().to_path_buf()),
+            compression: CompressionMode::None,
+            old_files: OldFiles::KeepOld,
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("previous errors"));
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("existing.txt")).unwrap(),
+        "kept"
+    );
+    assert_eq!(
+        fs::read_to_string(tempdir.path().join("new.txt")).unwrap(),
+        "archived"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_archive_overwrite_keeps_hard_links() {
+    let tempdir = tempdir().unwrap();
+    let file = tempdir.path().join("file.txt");
+    let link = tempdir.path().join("link.txt");
+    let tar_bytes = single_file_tar("file.txt", "archived");
+
+    for (old_files, expected_link) in [
+        (OldFiles::Replace, "original"),
+        (OldFiles::Overwrite, "archived"),
+    ] {
+        let _ = fs::remove_file(&link);
+        fs::write(&file, "original").unwrap();
+        fs::hard_link(&file, &link).unwrap();
+
+        extract_archive(
+            tar_bytes.as_slice(),
+            Path::new("archive.tar"),
+            &ExtractOptions {
+                directory: Some(tempdir.path().to_path_buf()),
+                compression: CompressionMode::None,
+                old_files,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), "archived");
+        assert_eq!(fs::read_to_string(&link).unwrap(), expected_link);
+    }
+}
+
+#[test]
+fn test_file_mode() {
+    assert_eq!(file_mode(0o104755, true, 0o022), 0o4755);
+    assert_eq!(file_mode(0o104755, false, 0o022), 0o755);
+    assert_eq!(file_mode(0o100666, false, 0o027), 0o640);
+}
+
+#[test]
+fn test_strip_path_components() {
+    use crate::operations::strip_path_components;
+
+    assert_eq!(
+        strip_path_components(Path::new("a/b/c"), 0),
+        Some(PathBuf::from("a/b/c"))
+    );
+    assert_eq!(
+        strip_path_components(Path::new("a/b/c"), 2),
+        Some(PathBuf::from("c"))
+    );
+    assert_eq!(
+        strip_path_components(Path::new("/a/b"), 1),
+        Some(PathBuf::from("b"))
+    );
+    assert_eq!(
+        strip_path_components(Path::new("./a/b"), 1),
+        Some(PathBuf::from("a/b"))
+    );
+    assert_eq!(strip_path_components(Path::new("a/"), 1), None);
+    assert_eq!(strip_path_components(Path::new("a/b"), 3), None);
+}
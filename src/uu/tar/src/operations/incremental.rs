@@ -0,0 +1,335 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! GNU listed-incremental dumps (`-g`): snapshot files and the dumpdir
+//! members that list each directory's contents.
+
+use crate::errors::TarError;
+use crate::transform::{bytes_to_path, path_to_bytes};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Type flag of GNU dumpdir members
+pub const DUMPDIR: u8 = b'D';
+
+/// First line of a snapshot file, naming the format version (2)
+const SNAPSHOT_HEADER: &str = "GNU tar-1.35-2";
+
+/// Seconds and nanoseconds since the epoch
+pub(crate) type Timestamp = (u64, u32);
+
+/// The contents of a directory as stored in a dumpdir member or snapshot
+/// file: each name is tagged `Y` if it is in this dump, `N` if it is
+/// unchanged since the last one, or `D` if it is a directory.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Dumpdir(Vec<(u8, Vec<u8>)>);
+
+impl Dumpdir {
+    pub fn push(&mut self, tag: u8, name: &OsStr) {
+        self.0
+            .push((tag, path_to_bytes(Path::new(name)).into_owned()));
+    }
+
+    /// Whether `name` is listed, whatever its tag.
+    pub fn contains(&self, name: &OsStr) -> bool {
+        let name = path_to_bytes(Path::new(name));
+        self.0.iter().any(|(_, listed)| *listed == *name)
+    }
+
+    /// Encode as `<tag><name>\0` per entry, sorted by name, followed by a
+    /// final `\0`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+        let mut bytes = Vec::new();
+        for (tag, name) in entries {
+            bytes.push(*tag);
+            bytes.extend_from_slice(name);
+            bytes.push(0);
+        }
+        bytes.push(0);
+        bytes
+    }
+
+    /// Decode the data of a dumpdir member.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        Self::from_fields(&mut data.split(|&b| b == 0))
+    }
+
+    /// Read entries up to the empty field that ends the listing.
+    fn from_fields<'a>(fields: &mut impl Iterator<Item = &'a [u8]>) -> Option<Self> {
+        let mut entries = Vec::new();
+        loop {
+            match fields.next()? {
+                [] => return Some(Self(entries)),
+                [tag, name @ ..] => entries.push((*tag, name.to_vec())),
+            }
+        }
+    }
+}
+
+/// A directory recorded in a snapshot file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DirectoryRecord {
+    /// Whether the directory is on NFS
+    pub nfs: bool,
+    pub mtime: Timestamp,
+    pub dev: u64,
+    pub ino: u64,
+    pub contents: Dumpdir,
+}
+
+/// The state written by a listed-incremental dump for the next one to
+/// compare against
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Snapshot {
+    /// When the dump started
+    pub time: Timestamp,
+    pub directories: BTreeMap<PathBuf, DirectoryRecord>,
+}
+
+impl Snapshot {
+    /// Read the snapshot at `path`.  A missing or empty file (such as
+    /// `/dev/null`) gives an empty snapshot, so that everything is dumped.
+    pub fn read(path: &Path) -> Result<Self, TarError> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(TarError::from_io_error(e, path)),
+        };
+        if data.is_empty() {
+            return Ok(Self::default());
+        }
+        Self::parse(&data).ok_or_else(|| TarError::InvalidSnapshot {
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Parse a version 2 snapshot:
+    ///
+    /// ```text
+    /// GNU tar-VERSION-2\n
+    /// SECONDS\0NANOSECONDS\0
+    /// then per directory:
+    /// NFS\0SECONDS\0NANOSECONDS\0DEV\0INO\0NAME\0DUMPDIR\0
+    /// ```
+    fn parse(data: &[u8]) -> Option<Self> {
+        let newline = data.iter().position(|&b| b == b'\n')?;
+        let header = std::str::from_utf8(&data[..newline]).ok()?;
+        if !header.starts_with("GNU tar-") || !header.ends_with("-2") {
+            return None;
+        }
+
+        let mut fields = data[newline + 1..].split(|&b| b == 0);
+        let mut snapshot = Self {
+            time: (number(fields.next()?)?, number(fields.next()?)?),
+            directories: BTreeMap::new(),
+        };
+        while let Some(nfs) = fields.next().filter(|field| !field.is_empty()) {
+            let nfs = match nfs {
+                b"0" => false,
+                b"1" => true,
+                _ => return None,
+            };
+            let mtime = (number(fields.next()?)?, number(fields.next()?)?);
+            let dev = number(fields.next()?)?;
+            let ino = number(fields.next()?)?;
+            let name = bytes_to_path(fields.next()?.to_vec());
+            let contents = Dumpdir::from_fields(&mut fields)?;
+            // Each record ends with one more NUL after its dumpdir
+            if !fields.next()?.is_empty() {
+                return None;
+            }
+            snapshot.directories.insert(
+                name,
+                DirectoryRecord {
+                    nfs,
+                    mtime,
+                    dev,
+                    ino,
+                    contents,
+                },
+            );
+        }
+        Some(snapshot)
+    }
+
+    /// Encode in the format read by [`Snapshot::parse`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes =
+            format!("{SNAPSHOT_HEADER}\n{}\0{}\0", self.time.0, self.time.1).into_bytes();
+        for (name, record) in &self.directories {
+            let fields = format!(
+                "{}\0{}\0{}\0{}\0{}\0",
+                u8::from(record.nfs),
+                record.mtime.0,
+                record.mtime.1,
+                record.dev,
+                record.ino
+            );
+            bytes.extend_from_slice(fields.as_bytes());
+            bytes.extend_from_slice(&path_to_bytes(name));
+            bytes.push(0);
+            bytes.extend_from_slice(&record.contents.to_bytes());
+            bytes.push(0);
+        }
+        bytes
+    }
+}
+
+fn number<T: std::str::FromStr>(field: &[u8]) -> Option<T> {
+    std::str::from_utf8(field).ok()?.parse().ok()
+}
+
+/// A listed-incremental dump in progress
+#[derive(Debug)]
+pub struct Incremental {
+    /// Where the snapshot is read from and written back to
+    path: PathBuf,
+    previous: Snapshot,
+    next: Snapshot,
+}
+
+impl Incremental {
+    /// Start a dump against the snapshot at `path`; without one, this is a
+    /// full (level 0) dump.
+    pub fn start(path: &Path) -> Result<Self, TarError> {
+        let previous = Snapshot::read(path)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Self {
+            path: path.to_path_buf(),
+            previous,
+            next: Snapshot {
+                time: (now.as_secs(), now.subsec_nanos()),
+                directories: BTreeMap::new(),
+            },
+        })
+    }
+
+    /// Record the directory `name` and work out which of its `children` go
+    /// into the dump, returning the dumpdir that lists all of them.
+    ///
+    /// Subdirectories are always kept so that their own contents are
+    /// checked.  Other files are kept if the directory is new since the
+    /// previous dump (or was replaced), or if they changed after it; those
+    /// that `stat` fails on are kept too, to report the error when they are
+    /// added.
+    pub fn scan_directory(
+        &mut self,
+        name: &Path,
+        meta: &fs::Metadata,
+        children: &mut Vec<fs::DirEntry>,
+        stat: impl Fn(&Path) -> io::Result<fs::Metadata>,
+    ) -> Dumpdir {
+        let (dev, ino) = file_id(meta);
+        let is_new = !matches!(
+            self.previous.directories.get(name),
+            Some(record) if (record.dev, record.ino) == (dev, ino)
+        );
+
+        let mut contents = Dumpdir::default();
+        children.retain(|child| {
+            let (tag, keep) = match stat(&child.path()) {
+                Ok(child_meta) if child_meta.is_dir() => (b'D', true),
+                Ok(child_meta) if !is_new && !self.is_changed(&child_meta) => (b'N', false),
+                _ => (b'Y', true),
+            };
+            contents.push(tag, &child.file_name());
+            keep
+        });
+
+        self.next.directories.insert(
+            name.to_path_buf(),
+            DirectoryRecord {
+                nfs: false,
+                mtime: modified(meta),
+                dev,
+                ino,
+                contents: contents.clone(),
+            },
+        );
+        contents
+    }
+
+    /// Whether the file was modified, or its inode changed, since the
+    /// previous dump started.
+    fn is_changed(&self, meta: &fs::Metadata) -> bool {
+        modified(meta).max(changed(meta)) >= self.previous.time
+    }
+
+    /// Write the snapshot for the next dump.
+    pub fn finish(&self) -> Result<(), TarError> {
+        fs::write(&self.path, self.next.to_bytes()).map_err(|e| TarError::CannotWriteSnapshot {
+            path: self.path.clone(),
+            source: e,
+        })
+    }
+}
+
+/// When the file's data was last modified.
+pub(crate) fn modified(meta: &fs::Metadata) -> Timestamp {
+    meta.modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or((0, 0), |elapsed| {
+            (elapsed.as_secs(), elapsed.subsec_nanos())
+        })
+}
+
+/// When the file's inode was last changed.
+#[cfg(unix)]
+pub(crate) fn changed(meta: &fs::Metadata) -> Timestamp {
+    use std::os::unix::fs::MetadataExt;
+
+    (
+        u64::try_from(meta.ctime()).unwrap_or(0),
+        u32::try_from(meta.ctime_nsec()).unwrap_or(0),
+    )
+}
+
+#[cfg(not(unix))]
+pub(crate) fn changed(meta: &fs::Metadata) -> Timestamp {
+    modified(meta)
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+
+    (meta.dev(), meta.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> (u64, u64) {
+    (0, 0)
+}
+
+/// Remove everything in `dir` that `contents` doesn't list, so that the
+/// directory matches its state at the time of the dump.
+pub fn purge_directory(dir: &Path, contents: &Dumpdir) -> io::Result<()> {
+    for child in fs::read_dir(dir)? {
+        let child = child?;
+        if contents.contains(&child.file_name()) {
+            continue;
+        }
+        if child.file_type()?.is_dir() {
+            fs::remove_dir_all(child.path())?;
+        } else {
+            fs::remove_file(child.path())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "incremental_tests.rs"]
+mod tests;
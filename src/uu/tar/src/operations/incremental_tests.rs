@@ -0,0 +1,152 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+use tempfile::tempdir;
+
+fn dumpdir(entries: &[(u8, &str)]) -> Dumpdir {
+    let mut dumpdir = Dumpdir::default();
+    for (tag, name) in entries {
+        dumpdir.push(*tag, OsStr::new(name));
+    }
+    dumpdir
+}
+
+#[test]
+fn test_dumpdir_round_trip() {
+    let contents = dumpdir(&[(b'Y', "b.txt"), (b'D', "sub"), (b'N', "a.txt")]);
+    let bytes = contents.to_bytes();
+    assert_eq!(bytes, b"Na.txt\0Yb.txt\0Dsub\0\0");
+
+    let parsed = Dumpdir::parse(&bytes).unwrap();
+    assert!(parsed.contains(OsStr::new("a.txt")));
+    assert!(parsed.contains(OsStr::new("sub")));
+    assert!(!parsed.contains(OsStr::new("c.txt")));
+    assert_eq!(Dumpdir::parse(b"Ya.txt"), None);
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    let mut snapshot = Snapshot {
+        time: (1_700_000_000, 5),
+        directories: BTreeMap::new(),
+    };
+    snapshot.directories.insert(
+        PathBuf::from("dir"),
+        DirectoryRecord {
+            nfs: false,
+            mtime: (1_600_000_000, 42),
+            dev: 2049,
+            ino: 131,
+            contents: dumpdir(&[(b'Y', "file"), (b'D', "sub")]),
+        },
+    );
+    snapshot.directories.insert(
+        PathBuf::from("dir/sub"),
+        DirectoryRecord {
+            nfs: false,
+            mtime: (1_600_000_001, 0),
+            dev: 2049,
+            ino: 132,
+            contents: Dumpdir::default(),
+        },
+    );
+
+    let bytes = snapshot.to_bytes();
+    assert!(bytes.starts_with(b"GNU tar-1.35-2\n1700000000\x005\x000\x00"));
+    assert_eq!(Snapshot::parse(&bytes), Some(snapshot));
+}
+
+#[test]
+fn test_snapshot_matches_gnu() {
+    // Written by GNU tar 1.34 for `data/f` and an empty `data/sub`, with
+    // the directories in name order
+    let records = concat!(
+        "0\x001792362688\x00132008832\x0065024\x002383947\x00data\x00Yf\x00Dsub\x00\x00\x00",
+        "0\x001792362688\x00124008832\x0065024\x002383948\x00data/sub\x00\x00\x00",
+    );
+    let gnu = format!("GNU tar-1.34-2\n1792362688\x00137423302\x00{records}");
+
+    let snapshot = Snapshot::parse(gnu.as_bytes()).unwrap();
+    assert_eq!(snapshot.time, (1_792_362_688, 137_423_302));
+    assert_eq!(
+        snapshot.directories[Path::new("data")],
+        DirectoryRecord {
+            nfs: false,
+            mtime: (1_792_362_688, 132_008_832),
+            dev: 65024,
+            ino: 2_383_947,
+            contents: dumpdir(&[(b'Y', "f"), (b'D', "sub")]),
+        }
+    );
+    assert_eq!(
+        snapshot.directories[Path::new("data/sub")].contents,
+        Dumpdir::default()
+    );
+    assert_eq!(
+        snapshot.to_bytes(),
+        format!("{SNAPSHOT_HEADER}\n1792362688\x00137423302\x00{records}").as_bytes()
+    );
+
+    // Without its terminator, a record runs into the next one
+    let unterminated = gnu.replacen("Dsub\x00\x00\x00", "Dsub\x00\x00", 1);
+    assert_eq!(Snapshot::parse(unterminated.as_bytes()), None);
+}
+
+#[test]
+fn test_snapshot_read() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("snapshot");
+
+    assert_eq!(Snapshot::read(&path).unwrap(), Snapshot::default());
+    fs::write(&path, "").unwrap();
+    assert_eq!(Snapshot::read(&path).unwrap(), Snapshot::default());
+
+    fs::write(&path, "GNU tar-1.35-1\n123\n").unwrap();
+    let err = Snapshot::read(&path).unwrap_err();
+    assert!(err.to_string().ends_with("Bad incremental file format"));
+    fs::write(&path, "GNU tar-1.35-2\n123\0x\0").unwrap();
+    assert!(Snapshot::read(&path).is_err());
+}
+
+#[test]
+fn test_scan_directory_selects_changed_files() {
+    let dir = tempdir().unwrap();
+    let tree = dir.path().join("tree");
+    fs::create_dir_all(tree.join("sub")).unwrap();
+    fs::write(tree.join("old.txt"), "old").unwrap();
+    let snapshot = dir.path().join("snapshot");
+    let read_children = || -> Vec<fs::DirEntry> {
+        let mut children: Vec<_> = fs::read_dir(&tree).unwrap().map(Result::unwrap).collect();
+        children.sort_by_key(fs::DirEntry::file_name);
+        children
+    };
+    let names = |children: &[fs::DirEntry]| -> Vec<_> {
+        children.iter().map(fs::DirEntry::file_name).collect()
+    };
+
+    // Level 0: everything is new
+    let mut level0 = Incremental::start(&snapshot).unwrap();
+    let mut children = read_children();
+    let meta = fs::metadata(&tree).unwrap();
+    let contents = level0.scan_directory(Path::new("tree"), &meta, &mut children, |p| {
+        fs::symlink_metadata(p)
+    });
+    assert_eq!(contents.to_bytes(), b"Yold.txt\0Dsub\0\0");
+    assert_eq!(names(&children), ["old.txt", "sub"]);
+    level0.finish().unwrap();
+
+    // Level 1: only files changed since level 0 are kept
+    let mut level1 = Incremental::start(&snapshot).unwrap();
+    // Past the granularity of file timestamps
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    fs::write(tree.join("new.txt"), "new").unwrap();
+    let mut children = read_children();
+    let contents = level1.scan_directory(Path::new("tree"), &meta, &mut children, |p| {
+        fs::symlink_metadata(p)
+    });
+    assert_eq!(contents.to_bytes(), b"Ynew.txt\0Nold.txt\0Dsub\0\0");
+    assert_eq!(names(&children), ["new.txt", "sub"]);
+}
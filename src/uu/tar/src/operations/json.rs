@@ -0,0 +1,187 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Machine-readable listing (`--list-format=json`): one JSON object per
+//! member on its own line (NDJSON), written as the archive is read.
+
+use crate::operations::incremental::DUMPDIR;
+use crate::operations::label::VOLUME_LABEL;
+use crate::volume::CONTINUATION;
+use std::fmt::Write as _;
+use std::io::{self, Read};
+use std::path::Path;
+use tar::{Entry, EntryType};
+
+/// Prefix of the pax records holding extended attributes
+const XATTR_PREFIX: &[u8] = b"SCHILY.xattr.";
+
+/// Name of the member type in the `type` field
+fn type_name(entry_type: EntryType) -> &'static str {
+    match entry_type {
+        EntryType::Regular | EntryType::GNUSparse => "file",
+        EntryType::Link => "hardlink",
+        EntryType::Symlink => "symlink",
+        EntryType::Char => "character-device",
+        EntryType::Block => "block-device",
+        EntryType::Directory => "directory",
+        EntryType::Fifo => "fifo",
+        EntryType::Continuous => "contiguous",
+        _ if entry_type.as_byte() == DUMPDIR => "directory",
+        _ if entry_type.as_byte() == VOLUME_LABEL => "volume-label",
+        _ if entry_type.as_byte() == CONTINUATION => "continuation",
+        _ => "unknown",
+    }
+}
+
+/// Append `bytes` to `out` as a JSON string.  Bytes that are not UTF-8
+/// are replaced, as JSON strings cannot carry them.
+fn push_string(out: &mut String, bytes: &[u8]) {
+    out.push('"');
+    for c in String::from_utf8_lossy(bytes).chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Append `"key":` to `out`, after a comma unless it is the first field.
+fn push_key(out: &mut String, key: &str) {
+    if !out.ends_with('{') {
+        out.push(',');
+    }
+    push_string(out, key.as_bytes());
+    out.push(':');
+}
+
+fn push_number(out: &mut String, key: &str, value: Option<u64>) {
+    push_key(out, key);
+    match value {
+        Some(value) => {
+            let _ = write!(out, "{value}");
+        }
+        None => out.push_str("null"),
+    }
+}
+
+fn push_bytes(out: &mut String, key: &str, value: Option<&[u8]>) {
+    push_key(out, key);
+    match value {
+        Some(value) => push_string(out, value),
+        None => out.push_str("null"),
+    }
+}
+
+/// Split a pax time such as `1700000000.25` into seconds and nanoseconds.
+pub(crate) fn parse_pax_time(value: &[u8]) -> Option<(u64, u32)> {
+    let value = std::str::from_utf8(value).ok()?;
+    let (secs, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = format!("{:0<9}", &fraction[..fraction.len().min(9)]);
+    Some((secs.parse().ok()?, nanos.parse().ok()?))
+}
+
+/// Format the JSON object describing `entry`, listed as `name`.  Values
+/// from pax records take precedence over the header's, as they do on
+/// extraction.
+pub fn format_entry<R: Read>(entry: &mut Entry<'_, R>, name: &Path) -> io::Result<String> {
+    let mut records = Vec::new();
+    if let Some(extensions) = entry.pax_extensions()? {
+        for extension in extensions {
+            let extension = extension?;
+            records.push((
+                extension.key_bytes().to_vec(),
+                extension.value_bytes().to_vec(),
+            ));
+        }
+    }
+    let record = |key: &[u8]| {
+        records
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_slice())
+    };
+    let pax_number = |key: &[u8]| {
+        record(key)
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .and_then(|v| v.parse().ok())
+    };
+
+    let header = entry.header();
+    let entry_type = header.entry_type();
+    let (mtime, mtime_nsec) = record(b"mtime")
+        .and_then(parse_pax_time)
+        .unwrap_or((header.mtime().unwrap_or(0), 0));
+    let is_device = matches!(entry_type, EntryType::Char | EntryType::Block);
+
+    let mut out = String::from("{");
+    push_bytes(&mut out, "path", Some(name.as_os_str().as_encoded_bytes()));
+    push_key(&mut out, "type");
+    push_string(&mut out, type_name(entry_type).as_bytes());
+    push_key(&mut out, "mode");
+    push_string(
+        &mut out,
+        format!("{:04o}", header.mode().unwrap_or(0) & 0o7777).as_bytes(),
+    );
+    push_number(&mut out, "uid", pax_number(b"uid").or(header.uid().ok()));
+    push_number(&mut out, "gid", pax_number(b"gid").or(header.gid().ok()));
+    push_bytes(
+        &mut out,
+        "uname",
+        record(b"uname").or(header.username_bytes()),
+    );
+    push_bytes(
+        &mut out,
+        "gname",
+        record(b"gname").or(header.groupname_bytes()),
+    );
+    push_number(&mut out, "size", Some(entry.size()));
+    push_number(&mut out, "mtime", Some(mtime));
+    push_number(&mut out, "mtime_nsec", Some(u64::from(mtime_nsec)));
+    push_bytes(&mut out, "link", entry.link_name_bytes().as_deref());
+    let device = |number: io::Result<Option<u32>>| {
+        is_device
+            .then(|| number.ok().flatten().map(u64::from))
+            .flatten()
+    };
+    push_number(&mut out, "devmajor", device(header.device_major()));
+    push_number(&mut out, "devminor", device(header.device_minor()));
+
+    push_key(&mut out, "xattrs");
+    out.push('[');
+    let xattrs = records
+        .iter()
+        .filter_map(|(key, _)| key.strip_prefix(XATTR_PREFIX));
+    for (i, xattr) in xattrs.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_string(&mut out, xattr);
+    }
+    out.push(']');
+
+    push_key(&mut out, "pax");
+    out.push('{');
+    for (key, value) in &records {
+        push_bytes(&mut out, &String::from_utf8_lossy(key), Some(value));
+    }
+    out.push_str("}}");
+    Ok(out)
+}
+
+#[cfg(test)]
+#[path = "json_tests.rs"]
+mod tests;
@@ -0,0 +1,135 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+use std::io::Cursor;
+use tar::{Archive, Builder, Header};
+
+/// Encode a pax record, whose length counts its own digits.
+fn pax_record(key: &str, value: &str) -> String {
+    let rest = format!(" {key}={value}\n");
+    let mut len = rest.len() + 1;
+    while format!("{len}").len() + rest.len() > len {
+        len += 1;
+    }
+    format!("{len}{rest}")
+}
+
+fn first_entry_json(archive: Vec<u8>, name: &str) -> String {
+    let mut archive = Archive::new(Cursor::new(archive));
+    let mut entries = archive.entries().unwrap();
+    let mut entry = entries.next().unwrap().unwrap();
+    format_entry(&mut entry, Path::new(name)).unwrap()
+}
+
+#[test]
+fn test_push_string_escapes() {
+    let mut out = String::new();
+    push_string(&mut out, b"a\"b\\c\nd\x01");
+    assert_eq!(out, r#""a\"b\\c\nd\u0001""#);
+}
+
+#[test]
+fn test_parse_pax_time() {
+    assert_eq!(parse_pax_time(b"1700000000"), Some((1_700_000_000, 0)));
+    assert_eq!(
+        parse_pax_time(b"1700000000.25"),
+        Some((1_700_000_000, 250_000_000))
+    );
+    assert_eq!(parse_pax_time(b"1.1234567891"), Some((1, 123_456_789)));
+    assert_eq!(parse_pax_time(b"soon"), None);
+}
+
+#[test]
+fn test_format_regular_member() {
+    let mut archive = Vec::new();
+    {
+        let mut builder = Builder::new(&mut archive);
+        let mut header = Header::new_gnu();
+        header.set_mode(0o100644);
+        header.set_uid(1000);
+        header.set_gid(100);
+        header.set_username("alice").unwrap();
+        header.set_groupname("staff").unwrap();
+        header.set_mtime(1_700_000_000);
+        header.set_size(5);
+        builder
+            .append_data(&mut header, "file.txt", Cursor::new("hello"))
+            .unwrap();
+        builder.finish().unwrap();
+    }
+    assert_eq!(
+        first_entry_json(archive, "file.txt"),
+        concat!(
+            r#"{"path":"file.txt","type":"file","mode":"0644","uid":1000,"gid":100,"#,
+            r#""uname":"alice","gname":"staff","size":5,"mtime":1700000000,"#,
+            r#""mtime_nsec":0,"link":null,"devmajor":null,"devminor":null,"#,
+            r#""xattrs":[],"pax":{}}"#
+        )
+    );
+}
+
+#[test]
+fn test_format_pax_records() {
+    let records = [
+        pax_record("mtime", "1700000000.5"),
+        pax_record("uname", "bob"),
+        pax_record("SCHILY.xattr.user.comment", "hi"),
+    ]
+    .concat();
+    let mut archive = Vec::new();
+    {
+        let mut builder = Builder::new(&mut archive);
+        let mut header = Header::new_ustar();
+        header.set_entry_type(EntryType::XHeader);
+        header.set_size(records.len() as u64);
+        builder
+            .append_data(&mut header, "PaxHeaders/link", records.as_bytes())
+            .unwrap();
+
+        let mut header = Header::new_ustar();
+        header.set_entry_type(EntryType::Symlink);
+        header.set_mode(0o777);
+        header.set_mtime(1_700_000_000);
+        header.set_size(0);
+        builder.append_link(&mut header, "link", "target").unwrap();
+        builder.finish().unwrap();
+    }
+    let json = first_entry_json(archive, "link");
+    assert!(json.contains(r#""type":"symlink","mode":"0777""#), "{json}");
+    assert!(json.contains(r#""uname":"bob""#), "{json}");
+    assert!(
+        json.contains(r#""mtime":1700000000,"mtime_nsec":500000000"#),
+        "{json}"
+    );
+    assert!(json.contains(r#""link":"target""#), "{json}");
+    assert!(json.contains(r#""xattrs":["user.comment"]"#), "{json}");
+    assert!(
+        json.contains(
+            r#""pax":{"mtime":"1700000000.5","uname":"bob","SCHILY.xattr.user.comment":"hi"}}"#
+        ),
+        "{json}"
+    );
+}
+
+#[test]
+fn test_format_device_numbers() {
+    let mut archive = Vec::new();
+    {
+        let mut builder = Builder::new(&mut archive);
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Char);
+        header.set_device_major(1).unwrap();
+        header.set_device_minor(3).unwrap();
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "null", std::io::empty())
+            .unwrap();
+        builder.finish().unwrap();
+    }
+    let json = first_entry_json(archive, "null");
+    assert!(json.contains(r#""type":"character-device""#), "{json}");
+    assert!(json.contains(r#""devmajor":1,"devminor":3"#), "{json}");
+}
@@ -0,0 +1,224 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Volume labels (`-V`): a GNU `V` header written as the first record of
+//! an archive, checked against a wildcard pattern when reading.
+
+use crate::compression::open_archive_reader;
+use crate::errors::TarError;
+use crate::operations::scanner::BlockScanner;
+use crate::record::BLOCK_SIZE;
+use crate::CompressionMode;
+use std::io::{self, Read, Write};
+use std::iter::Peekable;
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tar::{Archive, Entries, EntryType, Header};
+use uucore::error::{set_exit_code, UResult};
+
+/// Type flag of GNU volume label headers
+pub const VOLUME_LABEL: u8 = b'V';
+
+/// Longest label a header name field holds, leaving room for its NUL
+const LABEL_MAX: usize = 99;
+
+/// Mode, uid, gid and size: the numeric fields GNU tar leaves empty in the
+/// `V` headers it writes
+const EMPTY_LABEL_FIELDS: [Range<usize>; 4] = [100..108, 108..116, 116..124, 124..136];
+
+/// Build the `V` header recording `label`.
+pub fn label_header(label: &str) -> Result<Header, TarError> {
+    if label.len() > LABEL_MAX {
+        return Err(TarError::LabelTooLong { limit: LABEL_MAX });
+    }
+    let mut header = Header::new_gnu();
+    header.as_gnu_mut().unwrap().name[..label.len()].copy_from_slice(label.as_bytes());
+    header.set_entry_type(EntryType::new(VOLUME_LABEL));
+    header.set_mode(0);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_size(0);
+    header.set_mtime(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs()),
+    );
+    header.set_cksum();
+    Ok(header)
+}
+
+/// The label recorded by `header`, if it is a volume label.
+pub fn volume_label(header: &Header) -> Option<String> {
+    (header.entry_type().as_byte() == VOLUME_LABEL)
+        .then(|| String::from_utf8_lossy(&header.path_bytes()).into_owned())
+}
+
+/// Fill the numeric fields GNU tar leaves empty in a volume label with
+/// zeros, so that the `tar` crate can read past it.  Any other block, and
+/// a label whose checksum is wrong, is left alone.
+pub(crate) fn repair_label(block: &mut [u8; BLOCK_SIZE]) {
+    let header = Header::from_byte_slice(block);
+    // The checksum field itself counts as spaces
+    let sum = block[..148]
+        .iter()
+        .chain(&block[156..])
+        .fold(8 * u32::from(b' '), |sum, &b| sum + u32::from(b));
+    if header.entry_type().as_byte() != VOLUME_LABEL || header.cksum().ok() != Some(sum) {
+        return;
+    }
+
+    let mut repaired = header.clone();
+    let bytes = repaired.as_mut_bytes();
+    for range in EMPTY_LABEL_FIELDS {
+        let field = &mut bytes[range];
+        if field.iter().all(|&b| b == 0 || b == b' ') {
+            let last = field.len() - 1;
+            field[..last].fill(b'0');
+            field[last] = 0;
+        }
+    }
+    repaired.set_cksum();
+    block.copy_from_slice(repaired.as_bytes());
+}
+
+/// Check that an archive whose first member is `first` carries a label
+/// matching `pattern`.
+pub fn check_label(pattern: &str, first: Option<&Header>) -> Result<(), TarError> {
+    match first.and_then(volume_label) {
+        None => Err(TarError::NotLabeled {
+            pattern: pattern.to_string(),
+        }),
+        Some(label) if wildcard_match(pattern.as_bytes(), label.as_bytes()) => Ok(()),
+        Some(label) => Err(TarError::LabelMismatch {
+            label,
+            pattern: pattern.to_string(),
+        }),
+    }
+}
+
+/// Check the label of the archive whose members `entries` reads, before
+/// any of them is consumed.
+pub fn check_first_label<R: Read>(
+    pattern: &str,
+    entries: &mut Peekable<Entries<'_, R>>,
+) -> Result<(), TarError> {
+    match entries.peek() {
+        // A damaged first member is reported when it is read
+        Some(Err(_)) => Ok(()),
+        Some(Ok(entry)) => check_label(pattern, Some(entry.header())),
+        None => check_label(pattern, None),
+    }
+}
+
+/// Print the label of an archive (`--test-label`).  With `patterns`,
+/// nothing is printed and the exit status is 1 unless the label matches
+/// one of them.
+pub fn test_label(
+    input: impl Read,
+    patterns: &[String],
+    compression: CompressionMode,
+) -> UResult<()> {
+    let reader = open_archive_reader(input, compression)?;
+    let mut archive = Archive::new(BlockScanner::new(reader));
+    let label = match archive
+        .entries()
+        .map_err(TarError::CannotReadEntries)?
+        .next()
+    {
+        Some(entry) => volume_label(entry.map_err(TarError::CannotReadEntry)?.header()),
+        None => None,
+    };
+
+    if patterns.is_empty() {
+        if let Some(label) = &label {
+            writeln!(io::stdout(), "{label}")?;
+        }
+        return Ok(());
+    }
+    let matched = label.is_some_and(|label| {
+        patterns
+            .iter()
+            .any(|pattern| wildcard_match(pattern.as_bytes(), label.as_bytes()))
+    });
+    if !matched {
+        set_exit_code(1);
+    }
+    Ok(())
+}
+
+/// Match `text` against a shell wildcard `pattern`: `*`, `?`, bracket
+/// expressions and backslash escapes.
+pub(crate) fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*` when a later part fails to match
+    let mut backtrack = None;
+    while t < text.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            backtrack = Some((p, t));
+            continue;
+        }
+        if let Some(next) = match_one(pattern, p, text[t]) {
+            p = next;
+            t += 1;
+            continue;
+        }
+        match backtrack {
+            Some((star_p, star_t)) => {
+                p = star_p;
+                t = star_t + 1;
+                backtrack = Some((star_p, t));
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Match the single-character pattern element at `pattern[p]` against
+/// `c`, returning where the next element starts.
+fn match_one(pattern: &[u8], p: usize, c: u8) -> Option<usize> {
+    match *pattern.get(p)? {
+        b'?' => Some(p + 1),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then_some(p + 2),
+        b'[' => match match_bracket(pattern, p + 1, c) {
+            Some((true, next)) => Some(next),
+            Some((false, _)) => None,
+            // An unterminated bracket is an ordinary character
+            None => (c == b'[').then_some(p + 1),
+        },
+        literal => (literal == c).then_some(p + 1),
+    }
+}
+
+/// Match `c` against the bracket expression starting at `pattern[p]`,
+/// just after its `[`.  Returns whether it matched and where the
+/// expression ends, or `None` if it is never closed.
+fn match_bracket(pattern: &[u8], mut p: usize, c: u8) -> Option<(bool, usize)> {
+    let negated = matches!(pattern.get(p), Some(b'!' | b'^'));
+    if negated {
+        p += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let low = *pattern.get(p)?;
+        if low == b']' && !first {
+            return Some((matched != negated, p + 1));
+        }
+        first = false;
+        if pattern.get(p + 1) == Some(&b'-') && pattern.get(p + 2).is_some_and(|&b| b != b']') {
+            matched |= (low..=pattern[p + 2]).contains(&c);
+            p += 3;
+        } else {
+            matched |= low == c;
+            p += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "label_tests.rs"]
+mod tests;
@@ -0,0 +1,83 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+
+#[test]
+fn test_wildcard_match() {
+    assert!(wildcard_match(b"backup-*", b"backup-2026-10-18"));
+    assert!(wildcard_match(b"*", b""));
+    assert!(wildcard_match(b"disk?", b"disk7"));
+    assert!(wildcard_match(b"disk[0-9]", b"disk7"));
+    assert!(wildcard_match(b"disk[!0-9]", b"diskA"));
+    assert!(wildcard_match(b"a*b*c", b"axxbyybc"));
+    assert!(wildcard_match(b"\\*", b"*"));
+    assert!(wildcard_match(b"[]]", b"]"));
+    assert!(wildcard_match(b"x[", b"x["));
+
+    assert!(!wildcard_match(b"backup-*", b"restore-1"));
+    assert!(!wildcard_match(b"disk?", b"disk"));
+    assert!(!wildcard_match(b"disk[0-9]", b"diskA"));
+    assert!(!wildcard_match(b"\\*", b"a"));
+    assert!(!wildcard_match(b"a*c", b"abd"));
+}
+
+#[test]
+fn test_check_label() {
+    let header = label_header("Weekly 42").unwrap();
+    assert_eq!(volume_label(&header).as_deref(), Some("Weekly 42"));
+
+    assert!(check_label("Weekly *", Some(&header)).is_ok());
+    assert!(matches!(
+        check_label("Daily *", Some(&header)),
+        Err(TarError::LabelMismatch { .. })
+    ));
+    assert!(matches!(
+        check_label("*", None),
+        Err(TarError::NotLabeled { .. })
+    ));
+
+    let mut plain = Header::new_gnu();
+    plain.set_path("file.txt").unwrap();
+    assert!(matches!(
+        check_label("*", Some(&plain)),
+        Err(TarError::NotLabeled { .. })
+    ));
+}
+
+#[test]
+fn test_label_too_long() {
+    assert!(label_header(&"x".repeat(99)).is_ok());
+    assert!(matches!(
+        label_header(&"x".repeat(100)),
+        Err(TarError::LabelTooLong { limit: 99 })
+    ));
+}
+
+#[test]
+fn test_repair_label() {
+    // The fields GNU tar leaves empty in the labels it writes
+    let mut header = label_header("Daily 1").unwrap();
+    for range in EMPTY_LABEL_FIELDS {
+        header.as_mut_bytes()[range].fill(0);
+    }
+    header.set_cksum();
+    let mut block = *header.as_bytes();
+    assert!(header.entry_size().is_err());
+
+    repair_label(&mut block);
+    let repaired = Header::from_byte_slice(&block);
+    assert_eq!(repaired.entry_size().unwrap(), 0);
+    assert_eq!(repaired.mode().unwrap(), 0);
+    assert_eq!(volume_label(repaired).as_deref(), Some("Daily 1"));
+    assert_eq!(repaired.mtime().unwrap(), header.mtime().unwrap());
+
+    // A damaged label is left for the reader to report
+    let mut damaged = *header.as_bytes();
+    damaged[0] = b'X';
+    let before = damaged;
+    repair_label(&mut damaged);
+    assert_eq!(damaged, before);
+}
@@ -5,88 +5,272 @@
 
 use crate::compression::open_archive_reader;
 use crate::errors::TarError;
+use crate::operations::incremental::DUMPDIR;
+use crate::operations::json;
+use crate::operations::label::{check_first_label, VOLUME_LABEL};
+use crate::operations::mtree::{MtreeSpec, SIGNATURE};
+use crate::operations::scanner::{report_notices, BlockScanner};
+use crate::operations::strip_path_components;
+use crate::progress::{Direction, Meter, ProgressOptions};
+use crate::transform::{Transform, TransformScope};
+use crate::volume::{continued_at, CONTINUATION};
 use crate::CompressionMode;
-use chrono::{TimeZone, Utc};
+use chrono::{Local, TimeZone, Utc};
 use std::io::Read;
 use std::io::{self, BufWriter, Write};
 use std::path::Path;
-use tar::Archive;
+use tar::{Archive, EntryType, Header};
 use uucore::error::UResult;
 use uucore::fs::display_permissions_unix;
 
+/// How members are listed (`--list-format`)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ListFormat {
+    /// Names, or `ls -l` style lines with `-v`
+    #[default]
+    Text,
+    /// One JSON object per member and line, ignoring `-v`
+    Json,
+    /// An mtree spec of the members, ignoring `-v`
+    Mtree,
+}
+
+/// Options controlling archive listing
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    /// Print permissions, owner, size and date for each member (`-v`)
+    pub verbose: bool,
+    /// Compression of the archive stream
+    pub compression: CompressionMode,
+    /// Leading path components to remove from member names
+    /// (`--strip-components`)
+    pub strip_components: usize,
+    /// Rewrite member names (`--transform`); only applied to the listing
+    /// when `show_transformed_names` is set
+    pub transform: Transform,
+    /// List names as they would be extracted after `--transform`
+    pub show_transformed_names: bool,
+    /// Wildcard pattern the volume label must match (`-V`)
+    pub label: Option<String>,
+    /// Read on past zero blocks, such as the end of each archive in a
+    /// concatenation (`-i`)
+    pub ignore_zeros: bool,
+    /// Show member times in UTC rather than local time (`--utc`)
+    pub utc: bool,
+    /// Show member times to the second (`--full-time`)
+    pub full_time: bool,
+    /// Show owners by uid and gid only (`--numeric-owner`)
+    pub numeric_owner: bool,
+    /// Output format of the listing
+    pub format: ListFormat,
+    /// Totals and checkpoints to report (`--totals`, `--checkpoint`)
+    pub progress: ProgressOptions,
+}
+
 /// List the contents of a tar archive, printing one entry per line.
-pub fn list_archive(
-    input: impl Read,
-    _archive_path: &Path,
-    verbose: bool,
-    compression: CompressionMode,
-) -> UResult<()> {
-    let reader = open_archive_reader(input, compression)?;
-    let mut archive = Archive::new(reader);
+///
+/// With a non-zero `strip_components`, names are shown as
+/// `--strip-components` would extract them, and members that would be
+/// skipped are left out.
+pub fn list_archive(input: impl Read, _archive_path: &Path, options: &ListOptions) -> UResult<()> {
+    let mut input = Meter::new(input, &options.progress, Direction::Read);
+    let scanner = BlockScanner::new(open_archive_reader(&mut input, options.compression)?);
+    let notices = scanner.notices();
+    let mut archive = Archive::new(scanner);
+    archive.set_ignore_zeros(options.ignore_zeros);
     let mut out = BufWriter::new(io::stdout().lock());
 
-    for entry_result in archive.entries().map_err(TarError::CannotReadEntries)? {
-        let entry = entry_result.map_err(TarError::CannotReadEntry)?;
+    let mut entries = archive
+        .entries()
+        .map_err(TarError::CannotReadEntries)?
+        .peekable();
+    if let Some(pattern) = &options.label {
+        check_first_label(pattern, &mut entries)?;
+    }
+
+    let mut long_format = LongFormat::new(options.utc, options.full_time, options.numeric_owner);
+    let mut mtree = MtreeSpec::default();
+    if options.format == ListFormat::Mtree {
+        writeln!(out, "{SIGNATURE}").map_err(TarError::Io)?;
+    }
+    let mut damaged = false;
+    for entry_result in entries {
+        let mut entry = entry_result.map_err(TarError::CannotReadEntry)?;
+        damaged |= report_notices(&notices, &mut out, options.verbose)?;
+        let path = entry.path().map_err(TarError::CannotReadEntryPath)?;
+        let Some(mut name) = strip_path_components(&path, options.strip_components) else {
+            continue;
+        };
+        if options.show_transformed_names {
+            match options.transform.apply_path(&name, TransformScope::Regular) {
+                Some(transformed) => name = transformed,
+                None => continue,
+            }
+        }
 
-        if verbose {
-            let formatted = format_verbose_entry(&entry)?;
+        if options.format == ListFormat::Json {
+            let formatted =
+                json::format_entry(&mut entry, &name).map_err(TarError::CannotReadEntry)?;
+            writeln!(out, "{formatted}").map_err(TarError::Io)?;
+        } else if options.format == ListFormat::Mtree {
+            mtree
+                .write_member(&mut out, &mut entry, &name)
+                .map_err(TarError::Io)?;
+        } else if options.verbose {
+            let formatted = long_format.format(
+                entry.header(),
+                entry.size(),
+                &name,
+                entry.link_name_bytes().as_deref(),
+            );
             writeln!(out, "{formatted}").map_err(TarError::Io)?;
         } else {
-            let path = entry.path().map_err(TarError::CannotReadEntryPath)?;
-            writeln!(out, "{}", path.display()).map_err(TarError::Io)?;
+            writeln!(out, "{}", name.display()).map_err(TarError::Io)?;
         }
     }
 
+    damaged |= report_notices(&notices, &mut out, options.verbose)?;
     out.flush().map_err(TarError::Io)?;
+    drop(archive);
+    input.finish()?;
+    if damaged {
+        return Err(TarError::PreviousErrors.into());
+    }
     Ok(())
 }
 
-fn format_verbose_entry<R: Read>(entry: &tar::Entry<'_, R>) -> Result<String, TarError> {
-    let (mode, entry_type, owner, group, size, mtime) = {
-        let header = entry.header();
-        (
-            header.mode().unwrap_or(0),
-            header.entry_type(),
-            header
-                .username()
-                .ok()
-                .flatten()
-                .unwrap_or_default()
-                .to_owned(),
-            header
-                .groupname()
-                .ok()
-                .flatten()
-                .unwrap_or_default()
-                .to_owned(),
-            header.size().unwrap_or(0),
-            header.mtime().unwrap_or(0),
-        )
-    };
-
-    let path = entry.path().map_err(TarError::CannotReadEntryPath)?;
-
-    let type_char = match entry_type {
-        tar::EntryType::Directory => 'd',
-        tar::EntryType::Symlink => 'l',
-        tar::EntryType::Char => 'c',
-        tar::EntryType::Block => 'b',
-        tar::EntryType::Fifo => 'p',
-        _ => '-',
-    };
-    let perm_str = display_permissions_unix(mode, false);
-    let permissions = format!("{type_char}{perm_str}");
-
-    let dt: chrono::DateTime<Utc> = Utc
-        .timestamp_opt(mtime as i64, 0)
-        .single()
-        .unwrap_or_else(Utc::now);
-    let date_str = dt.format("%Y-%m-%d %H:%M");
-
-    Ok(format!(
-        "{permissions} {owner}/{group} {size:>8} {date_str} {}",
-        path.display()
-    ))
+/// Columns of the long listing (`-tv`) as GNU tar lays them out.  The
+/// owner/size and date columns widen to fit the widest value seen so far,
+/// so that later lines line up with earlier ones where they can.
+pub(crate) struct LongFormat {
+    /// Show times in UTC rather than local time (`--utc`)
+    utc: bool,
+    /// Show times to the second (`--full-time`)
+    full_time: bool,
+    /// Show uid and gid even where names are recorded (`--numeric-owner`)
+    numeric_owner: bool,
+    /// Width of the `user/group size` column
+    ugs_width: usize,
+    /// Width of the date column
+    date_width: usize,
+}
+
+impl LongFormat {
+    pub(crate) fn new(utc: bool, full_time: bool, numeric_owner: bool) -> Self {
+        Self {
+            utc,
+            full_time,
+            numeric_owner,
+            ugs_width: 19,
+            date_width: "YYYY-MM-DD HH:MM".len(),
+        }
+    }
+
+    /// Format the line for the member `header` describes, listed as `name`.
+    /// `size` and `link_name` are those of the member, which extension
+    /// headers may have overridden.
+    pub(crate) fn format(
+        &mut self,
+        header: &Header,
+        size: u64,
+        name: &Path,
+        link_name: Option<&[u8]>,
+    ) -> String {
+        let entry_type = header.entry_type();
+        let type_byte = entry_type.as_byte();
+        let type_char = match entry_type {
+            EntryType::Regular | EntryType::GNUSparse
+                if name.as_os_str().as_encoded_bytes().ends_with(b"/") =>
+            {
+                'd'
+            }
+            EntryType::Regular | EntryType::GNUSparse => '-',
+            EntryType::Link => 'h',
+            EntryType::Directory => 'd',
+            EntryType::Symlink => 'l',
+            EntryType::Char => 'c',
+            EntryType::Block => 'b',
+            EntryType::Fifo => 'p',
+            EntryType::Continuous => 'C',
+            _ if type_byte == DUMPDIR => 'd',
+            _ if type_byte == VOLUME_LABEL => 'V',
+            _ if type_byte == CONTINUATION => 'M',
+            _ => '?',
+        };
+        let modes = display_permissions_unix(header.mode().unwrap_or(0), false);
+
+        let date = self.format_time(header.mtime().unwrap_or(0));
+        self.date_width = self.date_width.max(date.len());
+
+        let user = self.owner(header.username_bytes(), header.uid());
+        let group = self.owner(header.groupname_bytes(), header.gid());
+
+        let size = match entry_type {
+            EntryType::Char | EntryType::Block => format!(
+                "{},{}",
+                header.device_major().ok().flatten().unwrap_or(0),
+                header.device_minor().ok().flatten().unwrap_or(0)
+            ),
+            _ => size.to_string(),
+        };
+
+        let pad = user.len() + 1 + group.len() + 1 + size.len();
+        self.ugs_width = self.ugs_width.max(pad);
+        let size_width = self.ugs_width - pad + size.len();
+
+        let mut line = format!(
+            "{type_char}{modes} {user}/{group} {size:>size_width$} {date:<date_width$} {}",
+            name.display(),
+            date_width = self.date_width,
+        );
+        let link_name = String::from_utf8_lossy(link_name.unwrap_or_default());
+        match type_char {
+            'l' => line.push_str(&format!(" -> {}", link_name)),
+            'h' => line.push_str(&format!(" link to {}", link_name)),
+            'V' => line.push_str("--Volume Header--"),
+            'M' => line.push_str(&format!(
+                "--Continued at byte {}--",
+                continued_at(header).unwrap_or(0)
+            )),
+            '?' => line.push_str(&format!(" unknown file type '{}'", type_byte as char)),
+            _ => {}
+        }
+        line
+    }
+
+    /// The user or group recorded by name, or by number where there is no
+    /// name or names are not wanted.
+    fn owner(&self, name: Option<&[u8]>, id: io::Result<u64>) -> String {
+        match name {
+            Some(name) if !name.is_empty() && !self.numeric_owner => {
+                String::from_utf8_lossy(name).into_owned()
+            }
+            _ => id.unwrap_or(0).to_string(),
+        }
+    }
+
+    fn format_time(&self, mtime: u64) -> String {
+        let format = if self.full_time {
+            "%Y-%m-%d %H:%M:%S"
+        } else {
+            "%Y-%m-%d %H:%M"
+        };
+        let Ok(secs) = i64::try_from(mtime) else {
+            return mtime.to_string();
+        };
+        // A time the calendar cannot show is printed as a plain number
+        if self.utc {
+            Utc.timestamp_opt(secs, 0)
+                .single()
+                .map(|time| time.format(format).to_string())
+        } else {
+            Local
+                .timestamp_opt(secs, 0)
+                .single()
+                .map(|time| time.format(format).to_string())
+        }
+        .unwrap_or_else(|| mtime.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -33,7 +33,15 @@ fn test_list_archive_with_zstd_non_verbose() {
     write_zstd_tar(&archive_path);
 
     let input = fs::File::open(&archive_path).unwrap();
-    list_archive(input, &archive_path, false, CompressionMode::Zstd).unwrap();
+    list_archive(
+        input,
+        &archive_path,
+        &ListOptions {
+            compression: CompressionMode::Zstd,
+            ..Default::default()
+        },
+    )
+    .unwrap();
 }
 
 #[test]
@@ -43,5 +51,95 @@ fn test_list_archive_with_zstd_verbose() {
     write_zstd_tar(&archive_path);
 
     let input = fs::File::open(&archive_path).unwrap();
-    list_archive(input, &archive_path, true, CompressionMode::Zstd).unwrap();
+    list_archive(
+        input,
+        &archive_path,
+        &ListOptions {
+            verbose: true,
+            compression: CompressionMode::Zstd,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+}
+
+fn member_header(entry_type: tar::EntryType, owner: Option<(&str, &str)>) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_mode(0o644);
+    header.set_uid(1000);
+    header.set_gid(100);
+    header.set_mtime(1_700_000_000);
+    if let Some((user, group)) = owner {
+        header.set_username(user).unwrap();
+        header.set_groupname(group).unwrap();
+    }
+    header
+}
+
+#[test]
+fn test_long_format_matches_gnu_columns() {
+    let mut format = LongFormat::new(true, false, false);
+    let header = member_header(tar::EntryType::Regular, Some(("alice", "staff")));
+    assert_eq!(
+        format.format(&header, 42, Path::new("file.txt"), None),
+        "-rw-r--r-- alice/staff      42 2023-11-14 22:13 file.txt"
+    );
+
+    // A wider owner widens the column for the lines that follow
+    let header = member_header(
+        tar::EntryType::Regular,
+        Some(("averyveryverylongusername", "staff")),
+    );
+    assert_eq!(
+        format.format(&header, 42, Path::new("wide"), None),
+        "-rw-r--r-- averyveryverylongusername/staff 42 2023-11-14 22:13 wide"
+    );
+    let header = member_header(tar::EntryType::Regular, Some(("alice", "staff")));
+    assert_eq!(
+        format.format(&header, 7, Path::new("after"), None),
+        "-rw-r--r-- alice/staff                      7 2023-11-14 22:13 after"
+    );
+}
+
+#[test]
+fn test_long_format_numeric_owner() {
+    let mut format = LongFormat::new(true, false, false);
+    let header = member_header(tar::EntryType::Regular, None);
+    assert!(format
+        .format(&header, 0, Path::new("f"), None)
+        .starts_with("-rw-r--r-- 1000/100 "));
+
+    let mut format = LongFormat::new(true, false, true);
+    let header = member_header(tar::EntryType::Regular, Some(("alice", "staff")));
+    assert!(format
+        .format(&header, 0, Path::new("f"), None)
+        .starts_with("-rw-r--r-- 1000/100 "));
+}
+
+#[test]
+fn test_long_format_links_and_devices() {
+    let mut format = LongFormat::new(true, true, false);
+    let owner = Some(("root", "root"));
+
+    let mut header = member_header(tar::EntryType::Symlink, owner);
+    header.set_mode(0o777);
+    assert_eq!(
+        format.format(&header, 0, Path::new("sl"), Some(b"target")),
+        "lrwxrwxrwx root/root         0 2023-11-14 22:13:20 sl -> target"
+    );
+
+    let header = member_header(tar::EntryType::Link, owner);
+    assert_eq!(
+        format.format(&header, 0, Path::new("hl"), Some(b"target")),
+        "hrw-r--r-- root/root         0 2023-11-14 22:13:20 hl link to target"
+    );
+
+    let mut header = member_header(tar::EntryType::Char, owner);
+    header.set_device_major(1).unwrap();
+    header.set_device_minor(3).unwrap();
+    assert_eq!(
+        format.format(&header, 0, Path::new("null"), None),
+        "crw-r--r-- root/root       1,3 2023-11-14 22:13:20 null"
+    );
 }
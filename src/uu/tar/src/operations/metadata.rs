@@ -0,0 +1,425 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Overrides for the metadata stored with each member on create
+//! (`--owner`, `--group`, `--owner-map`, `--group-map`, `--mode`, `--mtime`,
+//! `--numeric-owner`).
+
+use crate::errors::TarError;
+use chrono::{DateTime, Local, Months, NaiveDate, NaiveDateTime, TimeDelta, TimeZone};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tar::Header;
+
+/// An owner or group to record for every member
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IdOverride {
+    /// Name to store; left empty if `None`
+    pub name: Option<String>,
+    pub id: u64,
+}
+
+/// Owners or groups to translate, read from `--owner-map` or `--group-map`
+#[derive(Clone, Debug, Default)]
+pub struct IdMap {
+    by_id: HashMap<u64, IdOverride>,
+    by_name: HashMap<String, IdOverride>,
+}
+
+impl IdMap {
+    /// The replacement for `id`, matched by number first and then by the
+    /// name `name_of` gives it.
+    fn lookup(&self, id: u64, name_of: impl FnOnce(u64) -> Option<String>) -> Option<&IdOverride> {
+        self.by_id.get(&id).or_else(|| {
+            if self.by_name.is_empty() {
+                return None;
+            }
+            self.by_name.get(&name_of(id)?)
+        })
+    }
+}
+
+/// A chmod-style mode change, such as `go-w,a+rX` or `0644`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ModeChange(String);
+
+impl ModeChange {
+    /// Apply the change to the permission bits of a member.
+    pub fn apply(&self, mode: u32, is_dir: bool) -> u32 {
+        // Validated when the option was parsed
+        self.try_apply(mode, is_dir).unwrap_or(mode)
+    }
+
+    #[cfg(not(windows))]
+    fn try_apply(&self, mode: u32, is_dir: bool) -> Result<u32, String> {
+        use uucore::mode::{parse_numeric, parse_symbolic};
+
+        self.0.split(',').try_fold(mode & 0o7777, |mode, clause| {
+            if clause.chars().any(|c| c.is_ascii_digit()) {
+                parse_numeric(mode, clause, is_dir)
+            } else {
+                parse_symbolic(mode, clause, 0, is_dir)
+            }
+        })
+    }
+
+    #[cfg(windows)]
+    fn try_apply(&self, _mode: u32, _is_dir: bool) -> Result<u32, String> {
+        Err("--mode is not supported on this platform".to_string())
+    }
+}
+
+/// User and group names looked up so far, by id, so that each owner costs
+/// one lookup however many files it has
+#[derive(Clone, Debug, Default)]
+pub struct NameCache {
+    users: RefCell<HashMap<u64, Option<String>>>,
+    groups: RefCell<HashMap<u64, Option<String>>>,
+}
+
+impl NameCache {
+    fn user(&self, id: u64) -> Option<String> {
+        let mut users = self.users.borrow_mut();
+        users.entry(id).or_insert_with(|| user_name(id)).clone()
+    }
+
+    fn group(&self, id: u64) -> Option<String> {
+        let mut groups = self.groups.borrow_mut();
+        groups.entry(id).or_insert_with(|| group_name(id)).clone()
+    }
+}
+
+/// Metadata forced onto every member instead of the values on disk
+#[derive(Clone, Debug, Default)]
+pub struct MetadataOverrides {
+    /// `--owner`
+    pub owner: Option<IdOverride>,
+    /// `--group`
+    pub group: Option<IdOverride>,
+    /// `--owner-map`; entries found here take precedence over `--owner`
+    pub owner_map: IdMap,
+    /// `--group-map`; entries found here take precedence over `--group`
+    pub group_map: IdMap,
+    /// `--mode`
+    pub mode: Option<ModeChange>,
+    /// `--mtime`, in seconds since the epoch
+    pub mtime: Option<u64>,
+    /// Only replace modification times later than `mtime` (`--clamp-mtime`)
+    pub clamp_mtime: bool,
+    /// Store ids without user and group names (`--numeric-owner`)
+    pub numeric_owner: bool,
+    /// Names of the owners and groups of the files on disk
+    pub names: NameCache,
+}
+
+impl MetadataOverrides {
+    /// Record the names of the owner and group of `header`'s ids, unless
+    /// only numbers are kept, and overwrite the fields that have an
+    /// override.
+    pub fn apply(&self, header: &mut Header) -> io::Result<()> {
+        if !self.numeric_owner {
+            // Names too long for the header are left out, as ids still work
+            let user = self.names.user(header.uid()?);
+            if let Some(name) = user.filter(|name| name.len() <= 32) {
+                header.set_username(&name)?;
+            }
+            let group = self.names.group(header.gid()?);
+            if let Some(name) = group.filter(|name| name.len() <= 32) {
+                header.set_groupname(&name)?;
+            }
+        }
+        let owner = self
+            .owner_map
+            .lookup(header.uid()?, |id| self.names.user(id));
+        if let Some(owner) = owner.or(self.owner.as_ref()) {
+            header.set_uid(owner.id);
+            header.set_username(owner.name.as_deref().unwrap_or(""))?;
+        }
+        let group = self
+            .group_map
+            .lookup(header.gid()?, |id| self.names.group(id));
+        if let Some(group) = group.or(self.group.as_ref()) {
+            header.set_gid(group.id);
+            header.set_groupname(group.name.as_deref().unwrap_or(""))?;
+        }
+        if self.numeric_owner {
+            header.set_username("")?;
+            header.set_groupname("")?;
+        }
+        if let Some(change) = &self.mode {
+            let is_dir = header.entry_type().is_dir();
+            header.set_mode(change.apply(header.mode()?, is_dir));
+        }
+        if let Some(mtime) = self.mtime {
+            if !self.clamp_mtime || header.mtime()? > mtime {
+                header.set_mtime(mtime);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse `--owner=NAME[:UID]`.
+pub fn parse_owner(value: &str) -> Result<IdOverride, String> {
+    parse_id(value, user_id, user_name).ok_or_else(|| format!("Invalid owner '{value}'"))
+}
+
+/// Parse `--group=NAME[:GID]`.
+pub fn parse_group(value: &str) -> Result<IdOverride, String> {
+    parse_id(value, group_id, group_name).ok_or_else(|| format!("Invalid group '{value}'"))
+}
+
+/// Read an `--owner-map` file.
+pub fn read_owner_map(path: &Path) -> Result<IdMap, TarError> {
+    read_id_map(path, user_id, user_name)
+}
+
+/// Read a `--group-map` file.
+pub fn read_group_map(path: &Path) -> Result<IdMap, TarError> {
+    read_id_map(path, group_id, group_name)
+}
+
+/// Read a map in GNU tar's format: one `OLD NEW[:ID]` pair per line, where
+/// `OLD` is a name or `+ID` and `NEW[:ID]` is read as for `--owner`.  Blank
+/// lines and `#` comments are ignored.
+fn read_id_map(
+    path: &Path,
+    id_of: fn(&str) -> Option<u64>,
+    name_of: fn(u64) -> Option<String>,
+) -> Result<IdMap, TarError> {
+    let content = fs::read_to_string(path).map_err(|e| TarError::from_io_error(e, path))?;
+    let mut map = IdMap::default();
+
+    for (index, line) in content.lines().enumerate() {
+        let entry = line.split('#').next().unwrap_or_default().trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let invalid = || TarError::InvalidMapEntry {
+            path: path.to_path_buf(),
+            line: index + 1,
+            entry: entry.to_string(),
+        };
+
+        let mut fields = entry.split_whitespace();
+        let (Some(key), Some(value), None) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(invalid());
+        };
+        let target = parse_id(value, id_of, name_of).ok_or_else(invalid)?;
+        match key.strip_prefix('+') {
+            Some(id) => {
+                map.by_id.insert(id.parse().map_err(|_| invalid())?, target);
+            }
+            None => {
+                map.by_name.insert(key.to_string(), target);
+            }
+        }
+    }
+    Ok(map)
+}
+
+/// Parse `NAME[:ID]`.
+///
+/// As in GNU tar, a lone value is looked up as a name first and taken as a
+/// number otherwise; a leading `+` forces the numeric reading.
+fn parse_id(
+    value: &str,
+    id_of: fn(&str) -> Option<u64>,
+    name_of: fn(u64) -> Option<String>,
+) -> Option<IdOverride> {
+    if let Some((name, id)) = value.split_once(':') {
+        return Some(IdOverride {
+            name: (!name.is_empty()).then(|| name.to_string()),
+            id: id.parse().ok()?,
+        });
+    }
+    let numeric = value.strip_prefix('+');
+    if numeric.is_none() {
+        if let Some(id) = id_of(value) {
+            return Some(IdOverride {
+                name: Some(value.to_string()),
+                id,
+            });
+        }
+    }
+    let id = numeric.unwrap_or(value).parse().ok()?;
+    Some(IdOverride {
+        name: name_of(id),
+        id,
+    })
+}
+
+#[cfg(unix)]
+fn user_id(name: &str) -> Option<u64> {
+    uucore::entries::usr2uid(name).ok().map(u64::from)
+}
+
+#[cfg(unix)]
+fn user_name(id: u64) -> Option<String> {
+    uucore::entries::uid2usr(u32::try_from(id).ok()?).ok()
+}
+
+#[cfg(unix)]
+fn group_id(name: &str) -> Option<u64> {
+    uucore::entries::grp2gid(name).ok().map(u64::from)
+}
+
+#[cfg(unix)]
+fn group_name(id: u64) -> Option<String> {
+    uucore::entries::gid2grp(u32::try_from(id).ok()?).ok()
+}
+
+#[cfg(not(unix))]
+fn user_id(_name: &str) -> Option<u64> {
+    None
+}
+
+#[cfg(not(unix))]
+fn user_name(_id: u64) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn group_id(_name: &str) -> Option<u64> {
+    None
+}
+
+#[cfg(not(unix))]
+fn group_name(_id: u64) -> Option<String> {
+    None
+}
+
+/// Parse `--mode`, checking that the change applies cleanly.
+pub fn parse_mode(value: &str) -> Result<ModeChange, String> {
+    let change = ModeChange(value.to_string());
+    change.try_apply(0o644, false)?;
+    change.try_apply(0o755, true)?;
+    Ok(change)
+}
+
+/// Parse `--pax-option=KEYWORD[=VALUE][,...]`.
+///
+/// Members are written in GNU format, which has no pax extended headers, so
+/// only `delete=PATTERN` is accepted: with no keywords stored there is
+/// nothing left for it to remove, and the output is already free of
+/// `atime`/`ctime` records.
+pub fn parse_pax_option(value: &str) -> Result<String, String> {
+    for option in value.split(',') {
+        match option.split_once('=') {
+            Some(("delete", pattern)) if !pattern.is_empty() => {}
+            _ => return Err(format!("Unsupported pax option '{option}'")),
+        }
+    }
+    Ok(value.to_string())
+}
+
+/// Parse `--mtime=DATE|FILE`, also used for `--newer`.
+///
+/// Values starting with `/` or `.` name a file whose modification time is
+/// used.  Dates may be given as `@SECONDS`, RFC 3339,
+/// `YYYY-MM-DD[ HH:MM[:SS]]` in local time, or relative to now as in
+/// `2 days ago` or `yesterday`.
+pub fn parse_mtime(value: &str) -> Result<u64, String> {
+    if value.starts_with('/') || value.starts_with('.') {
+        let modified = fs::metadata(value)
+            .and_then(|meta| meta.modified())
+            .map_err(|e| format!("{value}: Cannot stat: {e}"))?;
+        return modified
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .map_err(|_| format!("{value}: modification time is before 1970"));
+    }
+    parse_date(value)
+        .and_then(|secs| u64::try_from(secs).ok())
+        .ok_or_else(|| format!("Invalid date format '{value}'"))
+}
+
+/// Seconds since the epoch for a date in one of the formats accepted by
+/// `--mtime`.
+pub(crate) fn parse_date(value: &str) -> Option<i64> {
+    if let Some(secs) = value.strip_prefix('@') {
+        return secs.parse().ok();
+    }
+    if let Some(date) = parse_relative_date(value, Local::now()) {
+        return Some(date.timestamp());
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.timestamp());
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|date| date.timestamp())
+}
+
+/// A date relative to `now`, in the GNU style: `now`, `today`, `yesterday`,
+/// `tomorrow`, or a series of `[+-]N UNIT` items optionally followed by
+/// `ago`, such as `1 week 2 days ago` or `-3 hours`.
+fn parse_relative_date(value: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let value = value.trim().to_ascii_lowercase();
+    match value.as_str() {
+        "now" | "today" => return Some(now),
+        "yesterday" => return now.checked_sub_signed(TimeDelta::days(1)),
+        "tomorrow" => return now.checked_add_signed(TimeDelta::days(1)),
+        _ => {}
+    }
+
+    let mut words: Vec<&str> = value.split_whitespace().collect();
+    let sign = if words.pop_if(|word| *word == "ago").is_some() {
+        -1
+    } else {
+        1
+    };
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut date = now;
+    let mut words = words.into_iter();
+    while let Some(word) = words.next() {
+        let (count, unit) = match word.parse::<i64>() {
+            Ok(count) => (count, words.next()?),
+            Err(_) => (1, word),
+        };
+        let count = count.checked_mul(sign)?;
+        date = match unit.strip_suffix('s').unwrap_or(unit) {
+            "sec" | "second" => date.checked_add_signed(TimeDelta::try_seconds(count)?)?,
+            "min" | "minute" => date.checked_add_signed(TimeDelta::try_minutes(count)?)?,
+            "hour" => date.checked_add_signed(TimeDelta::try_hours(count)?)?,
+            "day" => date.checked_add_signed(TimeDelta::try_days(count)?)?,
+            "week" => date.checked_add_signed(TimeDelta::try_weeks(count)?)?,
+            "fortnight" => date.checked_add_signed(TimeDelta::try_weeks(count.checked_mul(2)?)?)?,
+            "month" => add_months(date, count)?,
+            "year" => add_months(date, count.checked_mul(12)?)?,
+            _ => return None,
+        };
+    }
+    Some(date)
+}
+
+fn add_months(date: DateTime<Local>, count: i64) -> Option<DateTime<Local>> {
+    let months = Months::new(u32::try_from(count.unsigned_abs()).ok()?);
+    if count < 0 {
+        date.checked_sub_months(months)
+    } else {
+        date.checked_add_months(months)
+    }
+}
+
+#[cfg(test)]
+#[path = "metadata_tests.rs"]
+mod tests;
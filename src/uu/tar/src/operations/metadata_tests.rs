@@ -0,0 +1,266 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+
+#[test]
+fn test_parse_owner_with_explicit_id() {
+    assert_eq!(
+        parse_owner("builder:1234").unwrap(),
+        IdOverride {
+            name: Some("builder".to_string()),
+            id: 1234,
+        }
+    );
+    assert_eq!(
+        parse_group(":42").unwrap(),
+        IdOverride { name: None, id: 42 }
+    );
+    assert!(parse_owner("builder:abc").is_err());
+}
+
+#[test]
+fn test_parse_owner_numeric() {
+    assert_eq!(parse_owner("+4321").unwrap().id, 4321);
+    assert_eq!(parse_owner("4321").unwrap().id, 4321);
+    assert!(parse_owner("no-such-user-here").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_parse_owner_by_name() {
+    assert_eq!(
+        parse_owner("root").unwrap(),
+        IdOverride {
+            name: Some("root".to_string()),
+            id: 0,
+        }
+    );
+    assert_eq!(parse_owner("+0").unwrap().name.as_deref(), Some("root"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_mode_change() {
+    let change = parse_mode("go-w,a+rX").unwrap();
+    assert_eq!(change.apply(0o100664, false), 0o644);
+    assert_eq!(change.apply(0o600, false), 0o644);
+    assert_eq!(change.apply(0o700, false), 0o755);
+    assert_eq!(change.apply(0o600, true), 0o755);
+    assert_eq!(parse_mode("0600").unwrap().apply(0o755, false), 0o600);
+    assert!(parse_mode("a+q").is_err());
+    assert!(parse_mode("u+r,").is_err());
+}
+
+#[test]
+fn test_parse_mtime_dates() {
+    assert_eq!(parse_mtime("@1700000000").unwrap(), 1_700_000_000);
+    assert_eq!(parse_mtime("2023-11-14T22:13:20Z").unwrap(), 1_700_000_000);
+    assert_eq!(
+        parse_mtime("2023-11-14T23:13:20+01:00").unwrap(),
+        1_700_000_000
+    );
+    assert!(parse_mtime("2023-11-14").is_ok());
+    assert!(parse_mtime("yesterday-ish").is_err());
+    assert!(parse_mtime("@-5").is_err());
+}
+
+#[test]
+fn test_parse_relative_dates() {
+    let now = Local.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+    let relative = |value| parse_relative_date(value, now);
+
+    assert_eq!(relative("now"), Some(now));
+    assert_eq!(
+        relative("yesterday"),
+        Some(Local.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap())
+    );
+    assert_eq!(
+        relative("2 days ago"),
+        Some(Local.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap())
+    );
+    assert_eq!(
+        relative("1 week 3 hours ago"),
+        Some(Local.with_ymd_and_hms(2026, 10, 11, 9, 0, 0).unwrap())
+    );
+    assert_eq!(
+        relative("-1 month"),
+        Some(Local.with_ymd_and_hms(2026, 9, 18, 12, 0, 0).unwrap())
+    );
+    assert_eq!(
+        relative("+30 min"),
+        Some(Local.with_ymd_and_hms(2026, 10, 18, 12, 30, 0).unwrap())
+    );
+    assert_eq!(relative("3 parsecs ago"), None);
+    assert_eq!(relative("ago"), None);
+    assert_eq!(relative("2026-10-01 00:00"), None);
+}
+
+#[test]
+fn test_parse_mtime_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let reference = dir.path().join("reference");
+    fs::write(&reference, "").unwrap();
+    let expected = fs::metadata(&reference)
+        .unwrap()
+        .modified()
+        .unwrap()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    assert_eq!(parse_mtime(reference.to_str().unwrap()).unwrap(), expected);
+    assert!(parse_mtime("./does-not-exist").is_err());
+}
+
+#[test]
+fn test_parse_pax_option() {
+    assert!(parse_pax_option("delete=atime,delete=ctime").is_ok());
+    assert!(parse_pax_option("delete=").is_err());
+    assert!(parse_pax_option("exthdr.name=%d/PaxHeaders/%f").is_err());
+}
+
+#[test]
+fn test_overrides_apply() {
+    let mut header = Header::new_gnu();
+    header.set_mode(0o100775);
+    header.set_uid(1000);
+    header.set_gid(1000);
+    header.set_mtime(2_000);
+
+    let overrides = MetadataOverrides {
+        owner: Some(IdOverride {
+            name: Some("root".to_string()),
+            id: 0,
+        }),
+        group: Some(IdOverride { name: None, id: 0 }),
+        mtime: Some(1_000),
+        clamp_mtime: true,
+        ..Default::default()
+    };
+    overrides.apply(&mut header).unwrap();
+
+    assert_eq!(header.uid().unwrap(), 0);
+    assert_eq!(header.username().unwrap(), Some("root"));
+    assert_eq!(header.gid().unwrap(), 0);
+    assert_eq!(header.mtime().unwrap(), 1_000);
+
+    // Clamping leaves older times alone
+    header.set_mtime(500);
+    overrides.apply(&mut header).unwrap();
+    assert_eq!(header.mtime().unwrap(), 500);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_overrides_record_owner_names() {
+    let mut header = Header::new_gnu();
+    header.set_mode(0o644);
+    header.set_uid(0);
+    header.set_gid(0);
+
+    let overrides = MetadataOverrides::default();
+    overrides.apply(&mut header).unwrap();
+    assert_eq!(header.username().unwrap(), user_name(0).as_deref());
+    assert_eq!(header.groupname().unwrap(), group_name(0).as_deref());
+    assert!(!header.username().unwrap().unwrap().is_empty());
+
+    // Each id is looked up once
+    assert_eq!(overrides.names.users.borrow().len(), 1);
+    overrides.apply(&mut header).unwrap();
+    assert_eq!(overrides.names.users.borrow().len(), 1);
+}
+
+#[test]
+fn test_overrides_numeric_owner() {
+    let mut header = Header::new_gnu();
+    header.set_mode(0o644);
+    header.set_uid(1000);
+    header.set_gid(1000);
+    header.set_username("builder").unwrap();
+    header.set_groupname("staff").unwrap();
+
+    let overrides = MetadataOverrides {
+        numeric_owner: true,
+        ..Default::default()
+    };
+    overrides.apply(&mut header).unwrap();
+
+    assert_eq!(header.uid().unwrap(), 1000);
+    assert_eq!(header.username().unwrap(), Some(""));
+    assert_eq!(header.groupname().unwrap(), Some(""));
+}
+
+#[test]
+fn test_read_id_map() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("owners");
+    fs::write(
+        &path,
+        "# local builders\n+1001 root:0\n\nbuilder  deploy:2000  # by name\n",
+    )
+    .unwrap();
+
+    let map = read_owner_map(&path).unwrap();
+    assert_eq!(
+        map.lookup(1001, |_| None),
+        Some(&IdOverride {
+            name: Some("root".to_string()),
+            id: 0,
+        })
+    );
+    assert_eq!(
+        map.lookup(7, |_| Some("builder".to_string())).unwrap().id,
+        2000
+    );
+    assert_eq!(map.lookup(7, |_| Some("other".to_string())), None);
+}
+
+#[test]
+fn test_read_id_map_reports_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("groups");
+    fs::write(&path, "+100 staff:100\n+abc wheel:0\n").unwrap();
+
+    let err = read_group_map(&path).unwrap_err();
+    assert!(matches!(err, TarError::InvalidMapEntry { line: 2, .. }));
+    assert!(err
+        .to_string()
+        .ends_with("groups:2: Invalid map entry '+abc wheel:0'"));
+
+    fs::write(&path, "staff\n").unwrap();
+    assert!(read_group_map(&path).is_err());
+    assert!(read_group_map(&dir.path().join("missing")).is_err());
+}
+
+#[test]
+fn test_overrides_apply_maps() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("owners");
+    fs::write(&path, "+1001 root:0\n").unwrap();
+
+    let overrides = MetadataOverrides {
+        owner: Some(IdOverride {
+            name: Some("nobody".to_string()),
+            id: 65534,
+        }),
+        owner_map: read_owner_map(&path).unwrap(),
+        ..Default::default()
+    };
+
+    // Mapped owners win over --owner, which covers everyone else
+    let mut header = Header::new_gnu();
+    header.set_mode(0o644);
+    header.set_uid(1001);
+    header.set_gid(1001);
+    overrides.apply(&mut header).unwrap();
+    assert_eq!(header.uid().unwrap(), 0);
+    assert_eq!(header.username().unwrap(), Some("root"));
+
+    header.set_uid(1002);
+    overrides.apply(&mut header).unwrap();
+    assert_eq!(header.uid().unwrap(), 65534);
+    assert_eq!(header.username().unwrap(), Some("nobody"));
+}
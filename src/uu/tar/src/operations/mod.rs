@@ -3,14 +3,61 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-#[cfg(test)]
-use std::path::{Path, PathBuf};
+use crate::errors::TarError;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
 #[cfg(test)]
 use std::sync::{Mutex, MutexGuard, OnceLock};
 
 pub mod create;
 pub mod extract;
+pub mod incremental;
+pub mod json;
+pub mod label;
 pub mod list;
+pub mod metadata;
+pub mod mtree;
+pub mod scanner;
+
+/// Check that `dir`, given with `-C`, is a directory we can work in.
+///
+/// The process working directory is never changed; callers resolve paths
+/// against `dir` instead so the library stays safe to use from threads.
+pub(crate) fn check_directory(dir: &Path) -> Result<(), TarError> {
+    match fs::metadata(dir) {
+        Ok(meta) if meta.is_dir() => Ok(()),
+        Ok(_) => Err(TarError::CannotChangeDirectory {
+            path: dir.to_path_buf(),
+            source: io::Error::from(io::ErrorKind::NotADirectory),
+        }),
+        Err(e) => Err(TarError::CannotChangeDirectory {
+            path: dir.to_path_buf(),
+            source: e,
+        }),
+    }
+}
+
+/// Remove the first `count` components from a member name
+/// (`--strip-components`).
+///
+/// A leading `/` does not count as a component.  Returns `None` when nothing
+/// is left, in which case the member should be skipped.
+pub(crate) fn strip_path_components(path: &Path, count: usize) -> Option<PathBuf> {
+    if count == 0 {
+        return Some(path.to_path_buf());
+    }
+    let stripped: PathBuf = path
+        .components()
+        .filter(|c| !matches!(c, Component::Prefix(_) | Component::RootDir))
+        .skip(count)
+        .collect();
+    if stripped.as_os_str().is_empty() {
+        None
+    } else {
+        Some(stripped)
+    }
+}
 
 #[cfg(test)]
 pub(crate) fn test_cwd_lock() -> &'static Mutex<()> {
@@ -0,0 +1,228 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! mtree specs (`--format=mtree`, `--list-format=mtree`): one line per
+//! member in the BSD mtree(5) format, with a SHA-256 digest of each file,
+//! as bsdtar writes them, for checking a tree against a known-good spec.
+
+use crate::errors::TarError;
+use crate::operations::create::{create_archive, CreateOptions, FileOperand};
+use crate::operations::incremental::DUMPDIR;
+use crate::operations::json::parse_pax_time;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::UNIX_EPOCH;
+use tar::{Archive, Entry, EntryType};
+use uucore::error::UResult;
+
+/// First line of every spec
+pub const SIGNATURE: &str = "#mtree";
+
+/// Writes the lines of a spec, remembering the files seen so far so that
+/// hard links to them get their size and digest.
+#[derive(Debug, Default)]
+pub struct MtreeSpec {
+    files: HashMap<Vec<u8>, (u64, String)>,
+    /// Member name of each operand of an archive being made, as
+    /// [`bare_name`] has it, and where the operand is on disk
+    sources: Vec<(PathBuf, PathBuf)>,
+}
+
+impl MtreeSpec {
+    /// A spec of the archive `create_archive` makes of `files`, whose
+    /// modification times get back from disk the nanoseconds that tar
+    /// headers drop.
+    fn of_operands(files: &[FileOperand<'_>]) -> Self {
+        Self {
+            sources: files
+                .iter()
+                .map(|operand| (bare_name(operand.path), operand.source_path()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Nanoseconds of the modification time of the file on disk that was
+    /// archived as `name`, if it still falls in second `secs`.
+    fn disk_nanos(&self, name: &Path, secs: u64) -> Option<u32> {
+        let name = bare_name(name);
+        let source = self.sources.iter().find_map(|(member, source)| {
+            let rest = name.strip_prefix(member).ok()?;
+            Some(if rest.as_os_str().is_empty() {
+                source.clone()
+            } else {
+                source.join(rest)
+            })
+        })?;
+        let modified = fs::symlink_metadata(source).ok()?.modified().ok()?;
+        let since_epoch = modified.duration_since(UNIX_EPOCH).ok()?;
+        (since_epoch.as_secs() == secs).then(|| since_epoch.subsec_nanos())
+    }
+
+    /// Write the line for `entry`, listed as `name`, reading its data to
+    /// digest it.  Members with no place in a spec, such as volume labels,
+    /// are left out.
+    pub fn write_member<R: Read>(
+        &mut self,
+        out: &mut impl Write,
+        entry: &mut Entry<'_, R>,
+        name: &Path,
+    ) -> io::Result<()> {
+        let pax_mtime = match entry.pax_extensions()? {
+            Some(extensions) => extensions
+                .filter_map(Result::ok)
+                .filter(|extension| extension.key_bytes() == b"mtime")
+                .last()
+                .and_then(|extension| parse_pax_time(extension.value_bytes())),
+            None => None,
+        };
+        let header = entry.header();
+        let entry_type = header.entry_type();
+        let kind = match entry_type {
+            EntryType::Regular | EntryType::GNUSparse | EntryType::Continuous => "file",
+            EntryType::Link => "file",
+            EntryType::Directory => "dir",
+            EntryType::Symlink => "link",
+            EntryType::Char => "char",
+            EntryType::Block => "block",
+            EntryType::Fifo => "fifo",
+            _ if entry_type.as_byte() == DUMPDIR => "dir",
+            _ => return Ok(()),
+        };
+        let (secs, nanos) = pax_mtime.unwrap_or_else(|| {
+            let secs = header.mtime().unwrap_or(0);
+            (secs, self.disk_nanos(name, secs).unwrap_or(0))
+        });
+        let mut line = format!(
+            "{} time={secs}.{nanos} mode={:o} gid={} uid={} type={kind}",
+            spec_name(name.as_os_str().as_encoded_bytes()),
+            header.mode().unwrap_or(0) & 0o7777,
+            header.gid().unwrap_or(0),
+            header.uid().unwrap_or(0),
+        );
+
+        match entry_type {
+            EntryType::Symlink => {
+                let target = entry.link_name_bytes().unwrap_or_default();
+                line.push_str(&format!(" link={}", escape(&target)));
+            }
+            // A hard link has no data of its own: it shares its target's
+            EntryType::Link => {
+                let target = entry.link_name_bytes().unwrap_or_default();
+                if let Some((size, digest)) = self.files.get(target.as_ref()) {
+                    line.push_str(&format!(" size={size} sha256digest={digest}"));
+                }
+            }
+            _ if kind == "file" => {
+                let key = entry.path_bytes().into_owned();
+                let mut hasher = Sha256::new();
+                let size = io::copy(entry, &mut hasher)?;
+                let digest = format!("{:x}", hasher.finalize());
+                line.push_str(&format!(" size={size} sha256digest={digest}"));
+                self.files.insert(key, (size, digest));
+            }
+            _ => {}
+        }
+        writeln!(out, "{line}")
+    }
+}
+
+/// Escape the bytes mtree(5) cannot take literally: whitespace and other
+/// controls, non-ASCII bytes and its own `#`, `=` and `\`, as `\ooo`.
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for &b in bytes {
+        if b <= b' ' || b >= 0x7f || matches!(b, b'#' | b'=' | b'\\') {
+            escaped.push_str(&format!("\\{b:03o}"));
+        } else {
+            escaped.push(char::from(b));
+        }
+    }
+    escaped
+}
+
+/// Name a member the way mtree does: relative to `.` unless absolute,
+/// and without the trailing `/` of directories.
+fn spec_name(mut name: &[u8]) -> String {
+    while name.len() > 1 && name.ends_with(b"/") {
+        name = &name[..name.len() - 1];
+    }
+    if name == b"." || name.starts_with(b"/") || name.starts_with(b"./") {
+        escape(name)
+    } else {
+        format!("./{}", escape(name))
+    }
+}
+
+/// `name` without its root, `.` and `..` components, to match member names
+/// with operands whatever shape the tar crate stores them in.
+fn bare_name(name: &Path) -> PathBuf {
+    name.components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect()
+}
+
+/// Write `spec` of the uncompressed archive read from `input`.
+fn write_spec(mut input: impl Read, output: impl Write, spec: MtreeSpec) -> Result<(), TarError> {
+    let result = write_members(&mut input, output, spec);
+    // Take in the rest, such as the padding of the last record, so that
+    // whoever writes the archive never finds the pipe closed
+    io::copy(&mut input, &mut io::sink()).map_err(TarError::Io)?;
+    result
+}
+
+fn write_members(
+    input: impl Read,
+    output: impl Write,
+    mut spec: MtreeSpec,
+) -> Result<(), TarError> {
+    let mut out = BufWriter::new(output);
+    writeln!(out, "{SIGNATURE}").map_err(TarError::Io)?;
+    let mut archive = Archive::new(input);
+    for entry in archive.entries().map_err(TarError::CannotReadEntries)? {
+        let mut entry = entry.map_err(TarError::CannotReadEntry)?;
+        let name = entry
+            .path()
+            .map_err(TarError::CannotReadEntryPath)?
+            .into_owned();
+        spec.write_member(&mut out, &mut entry, &name)
+            .map_err(TarError::Io)?;
+    }
+    out.flush().map_err(TarError::Io)
+}
+
+/// Write the spec of the files `create_archive` would archive, in the
+/// order it would archive them (`-c --format=mtree`).  The archive is
+/// made as usual, with the same options, but read back into a spec as it
+/// is written rather than stored.  Modification times keep the
+/// nanoseconds of the files on disk, unless `--mtime` replaces them.
+pub fn create_spec(
+    output: impl Write + Send,
+    status_output: impl Write,
+    files: &[FileOperand<'_>],
+    options: &CreateOptions,
+) -> UResult<()> {
+    let spec = if options.overrides.mtime.is_some() && !options.overrides.clamp_mtime {
+        MtreeSpec::default()
+    } else {
+        MtreeSpec::of_operands(files)
+    };
+    let (reader, writer) = io::pipe().map_err(TarError::Io)?;
+    thread::scope(|scope| {
+        let spec = scope.spawn(move || write_spec(reader, output, spec));
+        let created = create_archive(writer, status_output, files, options);
+        // A failure to write the spec explains any broken pipe
+        spec.join().expect("mtree writer panicked")?;
+        created
+    })
+}
+
+#[cfg(test)]
+#[path = "mtree_tests.rs"]
+mod tests;
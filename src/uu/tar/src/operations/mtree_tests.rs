@@ -0,0 +1,84 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+use std::io::Cursor;
+use tar::{Builder, Header};
+
+#[test]
+fn test_escape() {
+    assert_eq!(escape(b"plain-name.txt"), "plain-name.txt");
+    assert_eq!(escape(b"sp ace"), "sp\\040ace");
+    assert_eq!(escape(b"a#b=c\\d"), "a\\043b\\075c\\134d");
+    assert_eq!(escape("é".as_bytes()), "\\303\\251");
+}
+
+#[test]
+fn test_spec_name() {
+    assert_eq!(spec_name(b"dir/file"), "./dir/file");
+    assert_eq!(spec_name(b"./file"), "./file");
+    assert_eq!(spec_name(b"dir/"), "./dir");
+    assert_eq!(spec_name(b"/abs"), "/abs");
+    assert_eq!(spec_name(b"."), ".");
+}
+
+#[test]
+fn test_write_spec() {
+    let mut archive = Vec::new();
+    {
+        let mut builder = Builder::new(&mut archive);
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_mtime(1_700_000_000);
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "dir/", io::empty())
+            .unwrap();
+
+        let mut header = Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_uid(1000);
+        header.set_gid(100);
+        header.set_mtime(1_700_000_000);
+        header.set_size(6);
+        builder
+            .append_data(&mut header, "dir/file", Cursor::new("hello\n"))
+            .unwrap();
+
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Link);
+        header.set_mode(0o644);
+        header.set_mtime(1_700_000_000);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "dir/hard", "dir/file")
+            .unwrap();
+
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Symlink);
+        header.set_mode(0o777);
+        header.set_mtime(1_700_000_000);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "dir/soft", "file")
+            .unwrap();
+        builder.finish().unwrap();
+    }
+
+    let mut spec = Vec::new();
+    write_spec(Cursor::new(archive), &mut spec, MtreeSpec::default()).unwrap();
+    let digest = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+    assert_eq!(
+        String::from_utf8(spec).unwrap(),
+        format!(
+            "#mtree\n\
+             ./dir time=1700000000.0 mode=755 gid=0 uid=0 type=dir\n\
+             ./dir/file time=1700000000.0 mode=644 gid=100 uid=1000 type=file size=6 sha256digest={digest}\n\
+             ./dir/hard time=1700000000.0 mode=644 gid=0 uid=0 type=file size=6 sha256digest={digest}\n\
+             ./dir/soft time=1700000000.0 mode=777 gid=0 uid=0 type=link link=file\n"
+        )
+    );
+}
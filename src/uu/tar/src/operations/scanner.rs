@@ -0,0 +1,272 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Follows the block structure of an archive as it is read, to report
+//! what the `tar` crate passes over silently, to recover from damaged
+//! headers by skipping to the next valid one, and to repair the GNU volume
+//! labels it cannot read.
+
+use crate::operations::label::repair_label;
+use crate::record::BLOCK_SIZE;
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+use tar::{EntryType, Header, PaxExtensions};
+
+/// Something worth a diagnostic seen while reading the archive
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScanNotice {
+    /// A single zero block was skipped where a header was due (`-i`).
+    /// As in GNU tar, `at` numbers the block after it.
+    LoneZeroBlock { at: u64 },
+    /// The archive does not start with a valid header
+    NotAnArchive,
+    /// A damaged header was found; blocks are skipped up to the next
+    /// valid one
+    Skipping,
+}
+
+impl ScanNotice {
+    /// Whether this is damage to the archive, which makes tar fail once
+    /// it has read what it can, rather than a warning for `-v`
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::LoneZeroBlock { .. })
+    }
+}
+
+impl fmt::Display for ScanNotice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LoneZeroBlock { at } => write!(f, "A lone zero block at {at}"),
+            Self::NotAnArchive => write!(f, "This does not look like a tar archive"),
+            Self::Skipping => write!(f, "Skipping to next header"),
+        }
+    }
+}
+
+/// Notices collected by a [`BlockScanner`], drained by whoever reads the
+/// entries of the archive
+pub type ScanNotices = Rc<RefCell<Vec<ScanNotice>>>;
+
+/// Report on stderr the notices collected so far, flushing `out` first
+/// so that they show up next to the member they precede.  Warnings are
+/// only shown when `verbose`.  Returns whether any damage was reported.
+pub fn report_notices(
+    notices: &ScanNotices,
+    out: &mut impl Write,
+    verbose: bool,
+) -> io::Result<bool> {
+    let pending: Vec<ScanNotice> = notices
+        .borrow_mut()
+        .drain(..)
+        .filter(|notice| verbose || notice.is_error())
+        .collect();
+    if pending.is_empty() {
+        return Ok(false);
+    }
+    out.flush()?;
+    let mut stderr = io::stderr().lock();
+    for notice in &pending {
+        writeln!(stderr, "tar: {notice}")?;
+    }
+    Ok(pending.iter().any(ScanNotice::is_error))
+}
+
+/// Whether `block` holds a header whose checksum is right
+fn checksum_ok(block: &[u8; BLOCK_SIZE]) -> bool {
+    // The checksum field itself counts as spaces
+    let sum = block[..148]
+        .iter()
+        .chain(&block[156..])
+        .fold(8 * u32::from(b' '), |sum, &b| sum + u32::from(b));
+    Header::from_byte_slice(block).cksum().ok() == Some(sum)
+}
+
+/// Whether `block` can be trusted as the header to resume at: it must
+/// carry the ustar or GNU magic as well as a good checksum.
+fn is_valid_header(block: &[u8; BLOCK_SIZE]) -> bool {
+    let header = Header::from_byte_slice(block);
+    (header.as_ustar().is_some() || header.as_gnu().is_some()) && checksum_ok(block)
+}
+
+/// The `size` recorded among pax extended header `records`, if any
+fn pax_size(records: &[u8]) -> Option<u64> {
+    PaxExtensions::new(records)
+        .flatten()
+        .find(|extension| extension.key() == Ok("size"))
+        .and_then(|extension| extension.value().ok()?.parse().ok())
+}
+
+/// Passes the archive through a block at a time, noting where headers
+/// and member data begin
+pub struct BlockScanner<R: Read> {
+    inner: R,
+    block: [u8; BLOCK_SIZE],
+    /// Bytes of `block` read from `inner`
+    len: usize,
+    /// Bytes of `block` handed out so far
+    pos: usize,
+    /// Number of the block in `block`, from 0
+    number: u64,
+    /// Data blocks still to come after the last header
+    data_blocks: u64,
+    /// Whether the data blocks to come hold a pax extended header
+    pax_data: bool,
+    /// Records of the last pax extended header, until the member it
+    /// describes
+    pax_records: Vec<u8>,
+    /// Whether the next block extends a GNU sparse header's map
+    sparse_extension: bool,
+    /// Zero blocks seen where a header was due: (first block, count)
+    zero_run: Option<(u64, u64)>,
+    /// Whether blocks are being dropped after a damaged header
+    skipping: bool,
+    notices: ScanNotices,
+}
+
+impl<R: Read> BlockScanner<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            block: [0; BLOCK_SIZE],
+            len: 0,
+            pos: 0,
+            number: 0,
+            data_blocks: 0,
+            pax_data: false,
+            pax_records: Vec::new(),
+            sparse_extension: false,
+            zero_run: None,
+            skipping: false,
+            notices: ScanNotices::default(),
+        }
+    }
+
+    /// The stream being scanned
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Where this scanner leaves its notices
+    pub fn notices(&self) -> ScanNotices {
+        Rc::clone(&self.notices)
+    }
+
+    /// Read the next block to pass on, or what is left of the input if
+    /// that is less.
+    fn next_block(&mut self) -> io::Result<()> {
+        loop {
+            if self.len == BLOCK_SIZE {
+                self.number += 1;
+            }
+            self.len = 0;
+            self.pos = 0;
+            while self.len < BLOCK_SIZE {
+                match self.inner.read(&mut self.block[self.len..]) {
+                    Ok(0) => break,
+                    Ok(n) => self.len += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            if self.len < BLOCK_SIZE {
+                // No header follows the damage; what is left is dropped too
+                if self.skipping {
+                    self.len = 0;
+                }
+                self.end_zero_run();
+                return Ok(());
+            }
+            if self.scan() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Follow the archive through the block just read.  Returns whether
+    /// to pass it on, which is not the case for damaged headers and the
+    /// blocks after them.
+    fn scan(&mut self) -> bool {
+        if self.sparse_extension {
+            // The last byte of the map says whether another block follows
+            self.sparse_extension = self.block[504] != 0;
+            return true;
+        }
+        if self.data_blocks > 0 {
+            self.data_blocks -= 1;
+            if self.pax_data {
+                self.pax_records.extend_from_slice(&self.block);
+            }
+            return true;
+        }
+        if self.block.iter().all(|&b| b == 0) {
+            // Damage that runs into the end of the archive stops there
+            self.skipping = false;
+            let (_, count) = self.zero_run.get_or_insert((self.number, 0));
+            *count += 1;
+            return true;
+        }
+
+        repair_label(&mut self.block);
+        if self.skipping {
+            if !is_valid_header(&self.block) {
+                return false;
+            }
+            self.skipping = false;
+        } else if !checksum_ok(&self.block) {
+            let mut notices = self.notices.borrow_mut();
+            if self.number == 0 {
+                notices.push(ScanNotice::NotAnArchive);
+            }
+            notices.push(ScanNotice::Skipping);
+            self.skipping = true;
+            return false;
+        }
+
+        self.end_zero_run();
+        let header = Header::from_byte_slice(&self.block);
+        let entry_type = header.entry_type();
+        let mut size = header.entry_size().unwrap_or(0);
+        self.pax_data = entry_type == EntryType::XHeader;
+        if self.pax_data {
+            self.pax_records.clear();
+        } else if !matches!(entry_type, EntryType::GNULongName | EntryType::GNULongLink) {
+            // A pax header before the member overrides the size it records
+            size = pax_size(&self.pax_records).unwrap_or(size);
+            self.pax_records.clear();
+        }
+        self.data_blocks = size.div_ceil(BLOCK_SIZE as u64);
+        self.sparse_extension = header.entry_type().is_gnu_sparse()
+            && header.as_gnu().is_some_and(|gnu| gnu.is_extended());
+        true
+    }
+
+    /// Note the zero blocks seen since the last header if there was only
+    /// one of them: a longer run is the end of an archive.
+    fn end_zero_run(&mut self) {
+        if let Some((first, 1)) = self.zero_run.take() {
+            self.notices
+                .borrow_mut()
+                .push(ScanNotice::LoneZeroBlock { at: first + 1 });
+        }
+    }
+}
+
+impl<R: Read> Read for BlockScanner<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.len {
+            self.next_block()?;
+        }
+        let n = buf.len().min(self.len - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+#[path = "scanner_tests.rs"]
+mod tests;
@@ -0,0 +1,124 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+use tar::Builder;
+
+fn archive_with(name: &str, data: &[u8]) -> Vec<u8> {
+    let mut builder = Builder::new(Vec::new());
+    let mut header = Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, name, data).unwrap();
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn test_scanner_passes_data_through() {
+    let archive = archive_with("a.txt", &[0; 600]);
+    let mut scanner = BlockScanner::new(archive.as_slice());
+    let mut copy = Vec::new();
+    scanner.read_to_end(&mut copy).unwrap();
+    assert_eq!(copy, archive);
+    // Zero-filled member data and the trailing end marker are not skipped
+    assert!(scanner.notices().borrow().is_empty());
+}
+
+fn notices_of(archive: &[u8]) -> Vec<ScanNotice> {
+    let mut scanner = BlockScanner::new(archive);
+    io::copy(&mut scanner, &mut io::sink()).unwrap();
+    let notices = scanner.notices().borrow().clone();
+    notices
+}
+
+#[test]
+fn test_scanner_notes_lone_zero_blocks() {
+    // A header and its data block, without the end of the archive
+    let member = archive_with("a.txt", b"a")[..2 * BLOCK_SIZE].to_vec();
+
+    let mut archive = member.clone();
+    archive.extend_from_slice(&[0; BLOCK_SIZE]);
+    archive.extend(archive_with("b.txt", b"b"));
+    assert_eq!(notices_of(&archive), [ScanNotice::LoneZeroBlock { at: 3 }]);
+
+    let mut truncated = member;
+    truncated.extend_from_slice(&[0; BLOCK_SIZE]);
+    assert_eq!(
+        notices_of(&truncated),
+        [ScanNotice::LoneZeroBlock { at: 3 }]
+    );
+
+    assert_eq!(
+        ScanNotice::LoneZeroBlock { at: 3 }.to_string(),
+        "A lone zero block at 3"
+    );
+}
+
+#[test]
+fn test_scanner_passes_over_archive_ends() {
+    let mut archive = archive_with("a.txt", b"a");
+    archive.extend(archive_with("b.txt", b"b"));
+    assert!(notices_of(&archive).is_empty());
+}
+
+#[test]
+fn test_scanner_skips_damaged_headers() {
+    let first = archive_with("a.txt", b"a");
+    let second = archive_with("b.txt", b"b");
+    let mut archive = first[..2 * BLOCK_SIZE].to_vec();
+    archive.extend_from_slice(&[0xaa; 2 * BLOCK_SIZE]);
+    archive.extend_from_slice(&second);
+
+    let mut scanner = BlockScanner::new(archive.as_slice());
+    let mut passed = Vec::new();
+    scanner.read_to_end(&mut passed).unwrap();
+    assert_eq!(passed, [&first[..2 * BLOCK_SIZE], &second[..]].concat());
+    assert_eq!(*scanner.notices().borrow(), [ScanNotice::Skipping]);
+}
+
+#[test]
+fn test_scanner_resyncs_only_on_magic() {
+    // A v7 header has a good checksum but no magic to be trusted by
+    let mut v7 = Header::new_old();
+    v7.set_path("v7.txt").unwrap();
+    v7.set_size(0);
+    v7.set_cksum();
+    let mut archive = vec![0xaa; BLOCK_SIZE];
+    archive.extend_from_slice(v7.as_bytes());
+    archive.extend(archive_with("b.txt", b"b"));
+
+    let mut scanner = BlockScanner::new(archive.as_slice());
+    let mut passed = Vec::new();
+    scanner.read_to_end(&mut passed).unwrap();
+    assert_eq!(passed, archive[2 * BLOCK_SIZE..]);
+    assert_eq!(
+        *scanner.notices().borrow(),
+        [ScanNotice::NotAnArchive, ScanNotice::Skipping]
+    );
+}
+
+#[test]
+fn test_scanner_follows_pax_sizes() {
+    // The pax size wins over the header's, as for members past 8 GiB
+    let mut builder = Builder::new(Vec::new());
+    let records = b"13 size=1024\n";
+    let mut pax = Header::new_ustar();
+    pax.set_entry_type(EntryType::XHeader);
+    pax.set_size(records.len() as u64);
+    pax.set_cksum();
+    builder.append(&pax, &records[..]).unwrap();
+    let mut big = Header::new_ustar();
+    big.set_path("big.bin").unwrap();
+    big.set_size(0);
+    big.set_cksum();
+    builder.append(&big, &[0xaa; 1024][..]).unwrap();
+    let archive = [builder.into_inner().unwrap(), archive_with("b.txt", b"b")].concat();
+
+    let mut scanner = BlockScanner::new(archive.as_slice());
+    let mut passed = Vec::new();
+    scanner.read_to_end(&mut passed).unwrap();
+    assert_eq!(passed, archive);
+    assert!(scanner.notices().borrow().is_empty());
+}
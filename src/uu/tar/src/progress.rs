@@ -0,0 +1,448 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Progress reports on long runs: the byte count at the end or on a signal
+//! (`--totals`), and actions taken every so many records (`--checkpoint`,
+//! `--checkpoint-action`).
+
+use crate::record::{BLOCK_SIZE, DEFAULT_BLOCKING_FACTOR};
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(unix)]
+use uucore::libc;
+
+/// Records between checkpoints when `--checkpoint` gives no number
+pub const DEFAULT_CHECKPOINT: u64 = 10;
+
+/// Signals `--totals=SIGNAL` and `wait=SIGNAL` accept, as GNU tar does
+#[cfg(unix)]
+const SIGNALS: [(&str, i32); 5] = [
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+];
+
+/// Set by the `--totals=SIGNAL` handler, cleared once totals are printed
+static TOTALS_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Parse a signal name, with or without its `SIG` prefix, or number.
+#[cfg(unix)]
+pub fn parse_signal(value: &str) -> Result<i32, String> {
+    let name = value.strip_prefix("SIG").unwrap_or(value);
+    SIGNALS
+        .iter()
+        .find(|(known, number)| *known == name || value.parse::<i32>() == Ok(*number))
+        .map(|&(_, number)| number)
+        .ok_or_else(|| format!("Unknown signal name: {value}"))
+}
+
+#[cfg(not(unix))]
+pub fn parse_signal(value: &str) -> Result<i32, String> {
+    Err(format!("Unknown signal name: {value}"))
+}
+
+/// Parse `--totals[=SIGNAL]`: `None` prints totals at the end, a signal
+/// prints them whenever it is delivered instead.
+pub fn parse_totals(value: &str) -> Result<Option<i32>, String> {
+    if value.is_empty() {
+        Ok(None)
+    } else {
+        parse_signal(value).map(Some)
+    }
+}
+
+/// Parse `--checkpoint[=N]`, a number of records.
+pub fn parse_checkpoint(value: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(records) if records > 0 => Ok(records),
+        _ => Err(format!("Invalid checkpoint '{value}'")),
+    }
+}
+
+/// What to do at each checkpoint (`--checkpoint-action`)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CheckpointAction {
+    /// Print a dot, without a newline (`dot`)
+    Dot,
+    /// Print a message on standard error, by default "Write checkpoint N"
+    /// (`echo[=STRING]`)
+    Echo(Option<String>),
+    /// Ring the terminal bell (`bell`)
+    Bell,
+    /// Print a message on the terminal (`ttyout=STRING`)
+    TtyOut(String),
+    /// Run a shell command and wait for it (`exec=COMMAND`)
+    Exec(String),
+    /// Pause for some seconds (`sleep=SECONDS`)
+    Sleep(u64),
+    /// Stop until a signal is delivered (`wait=SIGNAL`)
+    Wait(i32),
+}
+
+/// Parse one `--checkpoint-action`.
+pub fn parse_checkpoint_action(value: &str) -> Result<CheckpointAction, String> {
+    let (name, argument) = match value.split_once('=') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (value, None),
+    };
+    match (name, argument) {
+        ("dot" | ".", None) => Ok(CheckpointAction::Dot),
+        ("bell", None) => Ok(CheckpointAction::Bell),
+        ("echo", argument) => Ok(CheckpointAction::Echo(argument.map(str::to_string))),
+        ("ttyout", Some(text)) => Ok(CheckpointAction::TtyOut(text.to_string())),
+        ("exec", Some(command)) => Ok(CheckpointAction::Exec(command.to_string())),
+        ("sleep", Some(seconds)) => seconds
+            .parse()
+            .map(CheckpointAction::Sleep)
+            .map_err(|_| format!("{seconds}: not a valid timeout")),
+        ("wait", Some(signal)) => parse_signal(signal).map(CheckpointAction::Wait),
+        _ => Err(format!("{value}: unknown checkpoint action")),
+    }
+}
+
+/// Options controlling progress reports
+#[derive(Clone, Debug)]
+pub struct ProgressOptions {
+    /// Print the byte count once done (`--totals`)
+    pub totals: bool,
+    /// Print the byte count whenever this signal arrives (`--totals=SIGNAL`)
+    pub totals_signal: Option<i32>,
+    /// Records between checkpoints (`--checkpoint`)
+    pub checkpoint: Option<u64>,
+    /// What to do at each checkpoint, in order
+    pub checkpoint_actions: Vec<CheckpointAction>,
+    /// Passed to `exec` actions as `TAR_ARCHIVE`
+    pub archive: PathBuf,
+    /// Passed to `exec` actions as `TAR_SUBCOMMAND`
+    pub subcommand: &'static str,
+    /// Blocks per record (`-b`)
+    pub blocking_factor: usize,
+}
+
+impl Default for ProgressOptions {
+    fn default() -> Self {
+        Self {
+            totals: false,
+            totals_signal: None,
+            checkpoint: None,
+            checkpoint_actions: Vec::new(),
+            archive: PathBuf::new(),
+            subcommand: "",
+            blocking_factor: DEFAULT_BLOCKING_FACTOR,
+        }
+    }
+}
+
+/// Whether the archive is being read or written, for the wording of
+/// reports
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    Read,
+    Write,
+}
+
+/// Counts the archive data passing through it, as stored and so after any
+/// compression, a record at a time, and makes the reports `options` ask
+/// for.
+pub struct Meter<T> {
+    inner: T,
+    options: ProgressOptions,
+    direction: Direction,
+    bytes: u64,
+    /// Whole records passed so far
+    records: u64,
+    start: Instant,
+}
+
+impl<T> Meter<T> {
+    pub fn new(inner: T, options: &ProgressOptions, direction: Direction) -> Self {
+        if let Some(signal) = options.totals_signal {
+            request_totals_on(signal);
+        }
+        for action in &options.checkpoint_actions {
+            if let CheckpointAction::Wait(signal) = action {
+                // Blocked from the start, so that an early signal waits
+                // for its checkpoint rather than killing us
+                block_signal(*signal);
+            }
+        }
+        Self {
+            inner,
+            options: options.clone(),
+            direction,
+            bytes: 0,
+            records: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Print the totals if `--totals` asked for them, and return the
+    /// stream.
+    pub fn finish(self) -> io::Result<T> {
+        if self.options.totals {
+            self.print_totals()?;
+        }
+        Ok(self.inner)
+    }
+
+    fn record_size(&self) -> u64 {
+        (self.options.blocking_factor * BLOCK_SIZE) as u64
+    }
+
+    /// Count `n` more bytes, acting on every checkpoint they complete.
+    fn add(&mut self, n: usize) -> io::Result<()> {
+        self.bytes += n as u64;
+        let records = self.bytes / self.record_size();
+        while self.records < records {
+            self.records += 1;
+            if let Some(every) = self.options.checkpoint {
+                if self.records.is_multiple_of(every) {
+                    self.checkpoint()?;
+                }
+            }
+        }
+        if TOTALS_REQUESTED.swap(false, Ordering::Relaxed) {
+            self.print_totals()?;
+        }
+        Ok(())
+    }
+
+    /// Bytes read or written, as a number, its size in binary units and
+    /// the rate so far: "10240 (10KiB, 12MiB/s)".  Reads are counted in
+    /// whole records, as they are done.
+    fn totals(&self) -> String {
+        let bytes = match self.direction {
+            Direction::Write => self.bytes,
+            Direction::Read => self.bytes.div_ceil(self.record_size()) * self.record_size(),
+        };
+        let seconds = self.start.elapsed().as_secs_f64().max(f64::EPSILON);
+        format!(
+            "{bytes} ({}, {}/s)",
+            human_size(bytes as f64),
+            human_size(bytes as f64 / seconds)
+        )
+    }
+
+    fn print_totals(&self) -> io::Result<()> {
+        let verb = match self.direction {
+            Direction::Read => "read",
+            Direction::Write => "written",
+        };
+        writeln!(io::stderr(), "Total bytes {verb}: {}", self.totals())
+    }
+
+    fn checkpoint(&self) -> io::Result<()> {
+        if self.options.checkpoint_actions.is_empty() {
+            return self.run(&CheckpointAction::Echo(None));
+        }
+        for action in &self.options.checkpoint_actions {
+            self.run(action)?;
+        }
+        Ok(())
+    }
+
+    fn run(&self, action: &CheckpointAction) -> io::Result<()> {
+        match action {
+            CheckpointAction::Dot => {
+                let mut stderr = io::stderr();
+                write!(stderr, ".")?;
+                stderr.flush()
+            }
+            CheckpointAction::Echo(None) => {
+                let operation = match self.direction {
+                    Direction::Read => "Read",
+                    Direction::Write => "Write",
+                };
+                writeln!(io::stderr(), "tar: {operation} checkpoint {}", self.records)
+            }
+            CheckpointAction::Echo(Some(text)) => {
+                writeln!(io::stderr(), "tar: {}", self.expand(text))
+            }
+            // Without a terminal there is no one to tell
+            CheckpointAction::Bell => {
+                let _ = write_to_tty("\x07");
+                Ok(())
+            }
+            CheckpointAction::TtyOut(text) => {
+                let _ = write_to_tty(&self.expand(text));
+                Ok(())
+            }
+            CheckpointAction::Exec(command) => {
+                Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("TAR_VERSION", env!("CARGO_PKG_VERSION"))
+                    .env("TAR_ARCHIVE", &self.options.archive)
+                    .env("TAR_CHECKPOINT", self.records.to_string())
+                    .env(
+                        "TAR_BLOCKING_FACTOR",
+                        self.options.blocking_factor.to_string(),
+                    )
+                    .env("TAR_SUBCOMMAND", self.options.subcommand)
+                    .env("TAR_FORMAT", "gnu")
+                    .status()?;
+                Ok(())
+            }
+            CheckpointAction::Sleep(seconds) => {
+                thread::sleep(Duration::from_secs(*seconds));
+                Ok(())
+            }
+            CheckpointAction::Wait(signal) => {
+                wait_for_signal(*signal);
+                Ok(())
+            }
+        }
+    }
+
+    /// Expand the `%` sequences of an `echo` or `ttyout` message: `%s` is
+    /// the operation, `%u` the checkpoint number, `%T` the totals so far
+    /// and `%%` a percent sign.
+    fn expand(&self, text: &str) -> String {
+        let mut expanded = String::with_capacity(text.len());
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('s') => expanded.push_str(match self.direction {
+                    Direction::Read => "read",
+                    Direction::Write => "write",
+                }),
+                Some('u') => expanded.push_str(&self.records.to_string()),
+                Some('T') => {
+                    let prefix = match self.direction {
+                        Direction::Read => "R",
+                        Direction::Write => "W",
+                    };
+                    expanded.push_str(&format!("{prefix}: {}", self.totals()));
+                }
+                Some('%') => expanded.push('%'),
+                Some(other) => {
+                    expanded.push('%');
+                    expanded.push(other);
+                }
+                None => expanded.push('%'),
+            }
+        }
+        expanded
+    }
+}
+
+impl<R: Read> Read for Meter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.add(n)?;
+        Ok(n)
+    }
+}
+
+impl<W: Write> Write for Meter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.add(n)?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Format a size in binary units as GNU tar does, rounding up: "512B",
+/// "9.8MiB", "29MiB".
+fn human_size(bytes: f64) -> String {
+    const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        return format!("{}B", value.ceil());
+    }
+    // One decimal below 10, none above, as long as rounding up keeps it so
+    let tenths = (value * 10.0).ceil() / 10.0;
+    if tenths < 10.0 {
+        return format!("{tenths:.1}{}", UNITS[unit]);
+    }
+    let whole = value.ceil();
+    if whole >= 1024.0 && unit < UNITS.len() - 1 {
+        return format!("1.0{}", UNITS[unit + 1]);
+    }
+    format!("{whole}{}", UNITS[unit])
+}
+
+fn write_to_tty(text: &str) -> io::Result<()> {
+    let mut tty = OpenOptions::new().write(true).open("/dev/tty")?;
+    tty.write_all(text.as_bytes())
+}
+
+#[cfg(unix)]
+extern "C" fn totals_handler(_signal: libc::c_int) {
+    TOTALS_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Have `signal` request totals rather than end the process.
+#[cfg(unix)]
+fn request_totals_on(signal: i32) {
+    // SAFETY: the handler only stores to an atomic, which is async-signal
+    // safe
+    unsafe {
+        libc::signal(signal, totals_handler as *const () as libc::sighandler_t);
+    }
+}
+
+#[cfg(not(unix))]
+fn request_totals_on(_signal: i32) {}
+
+#[cfg(unix)]
+fn signal_set(signal: i32) -> libc::sigset_t {
+    // SAFETY: sigemptyset initializes the set before sigaddset reads it
+    unsafe {
+        let mut set = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, signal);
+        set
+    }
+}
+
+#[cfg(unix)]
+fn block_signal(signal: i32) {
+    let set = signal_set(signal);
+    // SAFETY: `set` is a valid signal set and the old mask is not wanted
+    unsafe {
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+    }
+}
+
+#[cfg(not(unix))]
+fn block_signal(_signal: i32) {}
+
+#[cfg(unix)]
+fn wait_for_signal(signal: i32) {
+    let set = signal_set(signal);
+    let mut received = 0;
+    // SAFETY: `set` is a valid signal set, blocked since `Meter::new`
+    unsafe {
+        libc::sigwait(&set, &mut received);
+    }
+}
+
+#[cfg(not(unix))]
+fn wait_for_signal(_signal: i32) {}
+
+#[cfg(test)]
+#[path = "progress_tests.rs"]
+mod tests;
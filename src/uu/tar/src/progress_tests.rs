@@ -0,0 +1,81 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+
+#[test]
+fn test_parse_checkpoint_action() {
+    assert_eq!(parse_checkpoint_action("dot"), Ok(CheckpointAction::Dot));
+    assert_eq!(parse_checkpoint_action("."), Ok(CheckpointAction::Dot));
+    assert_eq!(
+        parse_checkpoint_action("echo"),
+        Ok(CheckpointAction::Echo(None))
+    );
+    assert_eq!(
+        parse_checkpoint_action("echo=at %u"),
+        Ok(CheckpointAction::Echo(Some("at %u".to_string())))
+    );
+    assert_eq!(
+        parse_checkpoint_action("exec=true"),
+        Ok(CheckpointAction::Exec("true".to_string()))
+    );
+    assert_eq!(
+        parse_checkpoint_action("sleep=2"),
+        Ok(CheckpointAction::Sleep(2))
+    );
+    assert!(parse_checkpoint_action("sleep=soon").is_err());
+    assert!(parse_checkpoint_action("ttyout").is_err());
+    assert!(parse_checkpoint_action("jump").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_parse_signal() {
+    assert_eq!(parse_signal("SIGUSR1"), Ok(libc::SIGUSR1));
+    assert_eq!(parse_signal("HUP"), Ok(libc::SIGHUP));
+    assert_eq!(parse_signal(&libc::SIGINT.to_string()), Ok(libc::SIGINT));
+    assert!(parse_signal("SIGKILL").is_err());
+    assert_eq!(parse_totals(""), Ok(None));
+    assert_eq!(parse_totals("USR2"), Ok(Some(libc::SIGUSR2)));
+}
+
+#[test]
+fn test_human_size() {
+    assert_eq!(human_size(0.0), "0B");
+    assert_eq!(human_size(512.0), "512B");
+    assert_eq!(human_size(1024.0), "1.0KiB");
+    assert_eq!(human_size(10240.0), "10KiB");
+    assert_eq!(human_size(10_229_760.0), "9.8MiB");
+    assert_eq!(human_size(30_003_200.0), "29MiB");
+    assert_eq!(human_size(1_073_741_823.0), "1.0GiB");
+}
+
+#[test]
+fn test_meter_counts_records() {
+    let options = ProgressOptions {
+        checkpoint: Some(2),
+        checkpoint_actions: vec![CheckpointAction::Sleep(0)],
+        blocking_factor: 1,
+        ..Default::default()
+    };
+    let mut meter = Meter::new(Vec::new(), &options, Direction::Write);
+    meter.write_all(&[0; 3 * BLOCK_SIZE + 100]).unwrap();
+    assert_eq!(meter.records, 3);
+    assert_eq!(meter.expand("%s %u 100%%"), "write 3 100%");
+    assert!(meter.totals().starts_with("1636 (1.6KiB, "));
+    assert_eq!(meter.finish().unwrap().len(), 3 * BLOCK_SIZE + 100);
+}
+
+#[test]
+fn test_meter_counts_reads_in_whole_records() {
+    let options = ProgressOptions {
+        blocking_factor: 1,
+        ..Default::default()
+    };
+    let mut meter = Meter::new(&[0; 700][..], &options, Direction::Read);
+    io::copy(&mut meter, &mut io::sink()).unwrap();
+    assert!(meter.totals().starts_with("1024 (1.0KiB, "));
+    assert_eq!(meter.expand("%s"), "read");
+}
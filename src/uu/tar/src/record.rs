@@ -0,0 +1,151 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Records: the unit of I/O on the archive, `-b` blocks of 512 bytes
+//! (20 by default).
+
+use std::io::{self, Read, Write};
+
+pub const BLOCK_SIZE: usize = 512;
+
+/// Blocks per record unless `-b` or `--record-size` say otherwise
+pub const DEFAULT_BLOCKING_FACTOR: usize = 20;
+
+/// Parse a size given as a number of `unit`s, or with a `b` (512), `k`,
+/// `M`, `G` or `T` suffix.
+pub(crate) fn parse_size(value: &str, unit: u64) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, suffix) = value.split_at(split);
+    let multiplier: u64 = match suffix {
+        "" => unit,
+        "b" => 512,
+        "k" | "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return None,
+    };
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Parse `-b BLOCKS`, the number of 512-byte blocks per record.
+pub fn parse_blocking_factor(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(blocks) if (1..=(i32::MAX as usize) / BLOCK_SIZE).contains(&blocks) => Ok(blocks),
+        _ => Err(format!("Invalid blocking factor '{value}'")),
+    }
+}
+
+/// Parse `--record-size=BYTES` into a number of blocks per record.
+pub fn parse_record_size(value: &str) -> Result<usize, String> {
+    let bytes = parse_size(value, 1)
+        .filter(|&bytes| bytes > 0 && bytes <= i32::MAX as u64)
+        .ok_or_else(|| format!("Invalid record size '{value}'"))?;
+    if bytes % BLOCK_SIZE as u64 != 0 {
+        return Err(format!(
+            "{value}: Record size must be a multiple of {BLOCK_SIZE}."
+        ));
+    }
+    Ok(bytes as usize / BLOCK_SIZE)
+}
+
+/// Issues every write to the archive as one whole record
+pub(crate) struct RecordWriter<W: Write> {
+    inner: W,
+    record: Vec<u8>,
+    record_size: usize,
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub(crate) fn new(inner: W, record_size: usize) -> Self {
+        Self {
+            inner,
+            record: Vec::with_capacity(record_size),
+            record_size,
+        }
+    }
+
+    /// Pad the last record with zeros, as GNU tar does after the
+    /// end-of-archive blocks, and write it out.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        if !self.record.is_empty() {
+            self.record.resize(self.record_size, 0);
+            self.inner.write_all(&self.record)?;
+        }
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for RecordWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.record_size - self.record.len());
+        self.record.extend_from_slice(&buf[..n]);
+        if self.record.len() == self.record_size {
+            self.inner.write_all(&self.record)?;
+            self.record.clear();
+        }
+        Ok(n)
+    }
+
+    /// Flush what has been written as whole records; a partial record
+    /// waits for the rest of its data or for `finish`.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads the archive a whole record at a time (`-B`), reading again after
+/// short reads from pipes and tapes until each record is complete
+pub(crate) struct RecordReader<R: Read> {
+    inner: R,
+    record: Vec<u8>,
+    /// Bytes of `record` filled by the last refill
+    len: usize,
+    /// Bytes of `record` handed out so far
+    pos: usize,
+}
+
+impl<R: Read> RecordReader<R> {
+    pub(crate) fn new(inner: R, record_size: usize) -> Self {
+        Self {
+            inner,
+            record: vec![0; record_size],
+            len: 0,
+            pos: 0,
+        }
+    }
+
+    fn refill(&mut self) -> io::Result<()> {
+        self.len = 0;
+        self.pos = 0;
+        while self.len < self.record.len() {
+            match self.inner.read(&mut self.record[self.len..]) {
+                Ok(0) => break,
+                Ok(n) => self.len += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for RecordReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.len {
+            self.refill()?;
+        }
+        let n = buf.len().min(self.len - self.pos);
+        buf[..n].copy_from_slice(&self.record[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+#[path = "record_tests.rs"]
+mod tests;
@@ -0,0 +1,75 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+
+#[test]
+fn test_parse_record_options() {
+    assert_eq!(parse_blocking_factor("20").unwrap(), 20);
+    assert!(parse_blocking_factor("0").is_err());
+    assert!(parse_blocking_factor("x").is_err());
+
+    assert_eq!(parse_record_size("10240").unwrap(), 20);
+    assert_eq!(parse_record_size("64k").unwrap(), 128);
+    assert_eq!(
+        parse_record_size("1000").unwrap_err(),
+        "1000: Record size must be a multiple of 512."
+    );
+    assert!(parse_record_size("0").is_err());
+}
+
+/// Records every write it is given
+#[derive(Default)]
+struct Writes(Vec<Vec<u8>>);
+
+impl Write for Writes {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_record_writer_writes_whole_records() {
+    let mut writer = RecordWriter::new(Writes::default(), 1024);
+    writer.write_all(&[1; 700]).unwrap();
+    writer.write_all(&[2; 700]).unwrap();
+    writer.flush().unwrap();
+    let writes = writer.finish().unwrap().0;
+
+    assert_eq!(writes.len(), 2);
+    assert!(writes.iter().all(|record| record.len() == 1024));
+    assert_eq!(&writes[0][700..], &[2; 324]);
+    assert_eq!(&writes[1][..376], &[2; 376]);
+    assert!(writes[1][376..].iter().all(|&b| b == 0));
+}
+
+/// Hands out at most 100 bytes per read, like a pipe
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.0.len()).min(100);
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_record_reader_fills_records() {
+    let data: Vec<u8> = (0..1500u32).map(|n| n as u8).collect();
+    let mut reader = RecordReader::new(Trickle(&data), 1024);
+
+    let mut first = [0; 2048];
+    assert_eq!(reader.read(&mut first).unwrap(), 1024);
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!([&first[..1024], &rest[..]].concat(), data);
+}
@@ -6,20 +6,49 @@
 pub mod compression;
 pub mod errors;
 pub mod operations;
+pub mod progress;
+pub mod record;
+pub mod transform;
+pub mod volume;
 
 use crate::errors::TarError;
-use clap::{arg, crate_version, ArgAction, Command};
+use crate::operations::create::{CreateOptions, FileOperand, NewerThan, SortOrder};
+use crate::operations::extract::{ExtractOptions, OldFiles};
+use crate::operations::list::{ListFormat, ListOptions};
+use crate::operations::metadata::{
+    parse_group, parse_mode, parse_mtime, parse_owner, parse_pax_option, read_group_map,
+    read_owner_map, IdOverride, MetadataOverrides, ModeChange,
+};
+use crate::progress::{
+    parse_checkpoint, parse_checkpoint_action, parse_totals, CheckpointAction, ProgressOptions,
+    DEFAULT_CHECKPOINT,
+};
+use crate::record::{
+    parse_blocking_factor, parse_record_size, RecordReader, BLOCK_SIZE, DEFAULT_BLOCKING_FACTOR,
+};
+use crate::transform::Transform;
+use crate::volume::{parse_tape_length, VolumeReader, VolumeWriter, Volumes};
+use clap::{arg, crate_version, ArgAction, ArgMatches, Command};
+use std::env;
 use std::fs::File;
-use std::io::{self, IsTerminal};
+use std::io::{self, IsTerminal, Read};
 use std::path::{Path, PathBuf};
 use uucore::error::UResult;
 use uucore::format_usage;
 
 const ABOUT: &str = "an archiving utility";
+const OLD_FILES_OPTIONS: [&str; 5] = [
+    "keep-old-files",
+    "skip-old-files",
+    "keep-newer-files",
+    "overwrite",
+    "unlink-first",
+];
 const USAGE: &str = "tar key [FILE...]\n       tar {-c|-t|-x} [-v] -f ARCHIVE [FILE...]";
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum CompressionMode {
+    #[default]
     Auto,
     None,
     Gzip,
@@ -75,9 +104,7 @@ fn expand_posix_keystring(args: Vec<std::ffi::OsString>) -> Vec<std::ffi::OsStri
                 }
             }
             'b' => {
-                // Preserve parity with dash-style parsing by forwarding '-b'
-                // and its operand (when present). Since '-b' is currently
-                // unsupported, clap will report it as an unknown argument.
+                // Next file operand is the blocking factor
                 result.push(std::ffi::OsString::from("-b"));
                 if file_idx < file_operands.len() {
                     result.push(file_operands[file_idx].clone());
@@ -95,6 +122,212 @@ fn expand_posix_keystring(args: Vec<std::ffi::OsString>) -> Vec<std::ffi::OsStri
     result
 }
 
+/// Resolves every `-C` option to the directory it selects, keyed by its
+/// position on the command line.
+///
+/// As in GNU tar, a relative directory is taken relative to the one chosen
+/// by the previous `-C`, so `-C a -C b` selects `a/b`.
+fn resolve_directories(matches: &ArgMatches) -> Vec<(usize, PathBuf)> {
+    let (Some(indices), Some(values)) = (
+        matches.indices_of("directory"),
+        matches.get_many::<PathBuf>("directory"),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut current: Option<PathBuf> = None;
+    indices
+        .zip(values)
+        .map(|(index, dir)| {
+            let resolved = match &current {
+                Some(previous) => previous.join(dir),
+                None => dir.clone(),
+            };
+            current = Some(resolved.clone());
+            (index, resolved)
+        })
+        .collect()
+}
+
+/// Pairs each file operand with the `-C` directory in effect where it
+/// appears on the command line.
+fn file_operands<'a>(
+    matches: &'a ArgMatches,
+    directories: &'a [(usize, PathBuf)],
+) -> Vec<FileOperand<'a>> {
+    let (Some(indices), Some(values)) = (
+        matches.indices_of("files"),
+        matches.get_many::<PathBuf>("files"),
+    ) else {
+        return Vec::new();
+    };
+
+    indices
+        .zip(values)
+        .map(|(index, path)| FileOperand {
+            directory: directories
+                .iter()
+                .rev()
+                .find(|(dir_index, _)| *dir_index < index)
+                .map(|(_, dir)| dir.as_path()),
+            path: path.as_path(),
+        })
+        .collect()
+}
+
+/// The overwrite policy selected on the command line.  The options override
+/// one another, so at most one of them is set.
+fn old_files(matches: &ArgMatches) -> OldFiles {
+    [
+        ("keep-old-files", OldFiles::KeepOld),
+        ("skip-old-files", OldFiles::SkipOld),
+        ("keep-newer-files", OldFiles::KeepNewer),
+        ("overwrite", OldFiles::Overwrite),
+        ("unlink-first", OldFiles::UnlinkFirst),
+    ]
+    .into_iter()
+    .find(|(id, _)| matches.get_flag(id))
+    .map_or(OldFiles::Replace, |(_, policy)| policy)
+}
+
+/// The date given with `-N` or `--newer-mtime`, which override each other.
+fn newer_than(matches: &ArgMatches) -> Option<NewerThan> {
+    let (id, check_ctime) = if matches.contains_id("newer") {
+        ("newer", true)
+    } else {
+        ("newer-mtime", false)
+    };
+    matches
+        .get_one::<u64>(id)
+        .map(|&time| NewerThan { time, check_ctime })
+}
+
+/// The `SOURCE_DATE_EPOCH` of a reproducible build, if one is set.
+///
+/// Used as a `--clamp-mtime` limit when no `--mtime` is given, so that no
+/// member records a time later than the build's.
+fn source_date_epoch() -> UResult<Option<u64>> {
+    match env::var("SOURCE_DATE_EPOCH") {
+        Ok(value) if !value.is_empty() => value.parse().map(Some).map_err(|_| {
+            uucore::error::USimpleError::new(
+                2,
+                format!("Invalid SOURCE_DATE_EPOCH value '{value}'"),
+            )
+        }),
+        _ => Ok(None),
+    }
+}
+
+/// The archive named by the last `-f`.
+fn archive_path(matches: &ArgMatches) -> UResult<&PathBuf> {
+    matches
+        .get_many::<PathBuf>("file")
+        .and_then(Iterator::last)
+        .ok_or_else(|| uucore::error::USimpleError::new(64, "option requires an argument -- 'f'"))
+}
+
+/// Blocks per record, from `-b` or `--record-size`.
+fn blocking_factor(matches: &ArgMatches) -> usize {
+    matches
+        .get_one::<usize>("blocking-factor")
+        .or_else(|| matches.get_one::<usize>("record-size"))
+        .copied()
+        .unwrap_or(DEFAULT_BLOCKING_FACTOR)
+}
+
+/// Totals and checkpoints to report while `subcommand` reads or writes
+/// the archive.
+fn progress_options(matches: &ArgMatches, subcommand: &'static str) -> ProgressOptions {
+    let totals = matches.get_one::<Option<i32>>("totals");
+    let checkpoint_actions: Vec<CheckpointAction> = matches
+        .get_many::<CheckpointAction>("checkpoint-action")
+        .map(|actions| actions.cloned().collect())
+        .unwrap_or_default();
+    // An action alone means checkpoints at the default interval
+    let checkpoint = matches
+        .get_one::<u64>("checkpoint")
+        .copied()
+        .or_else(|| (!checkpoint_actions.is_empty()).then_some(DEFAULT_CHECKPOINT));
+    ProgressOptions {
+        totals: totals == Some(&None),
+        totals_signal: totals.copied().flatten(),
+        checkpoint,
+        checkpoint_actions,
+        archive: archive_path(matches).cloned().unwrap_or_default(),
+        subcommand,
+        blocking_factor: blocking_factor(matches),
+    }
+}
+
+/// Open the archive to read: the volumes of a multi-volume archive,
+/// standard input for `-f -`, or the file named by `-f`.
+fn archive_input(matches: &ArgMatches, subcommand: &'static str) -> UResult<Box<dyn Read>> {
+    let archive_path = archive_path(matches)?;
+    let input: Box<dyn Read> = if let Some(volumes) = volumes(matches, subcommand)? {
+        Box::new(VolumeReader::open(volumes)?)
+    } else if archive_path == Path::new("-") {
+        Box::new(io::stdin())
+    } else {
+        let file =
+            File::open(archive_path).map_err(|e| TarError::from_io_error(e, archive_path))?;
+        Box::new(file)
+    };
+    Ok(if matches.get_flag("read-full-records") {
+        let record_size = blocking_factor(matches) * BLOCK_SIZE;
+        Box::new(RecordReader::new(input, record_size))
+    } else {
+        input
+    })
+}
+
+/// The volumes of a multi-volume archive (`-M`, or implied by `-L` and
+/// `-F`): every `-f` name in turn.
+fn volumes(matches: &ArgMatches, subcommand: &'static str) -> UResult<Option<Volumes>> {
+    if !(matches.get_flag("multi-volume")
+        || matches.contains_id("tape-length")
+        || matches.contains_id("info-script"))
+    {
+        return Ok(None);
+    }
+    if matches.get_flag("gzip") || matches.get_flag("zstd") {
+        return Err(uucore::error::USimpleError::new(
+            2,
+            "Cannot use multi-volume compressed archives",
+        ));
+    }
+    let names: Vec<PathBuf> = matches
+        .get_many::<PathBuf>("file")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    if names.iter().any(|name| name == Path::new("-")) {
+        return Err(uucore::error::USimpleError::new(
+            2,
+            "Cannot use multi-volume archives on standard input or output",
+        ));
+    }
+    let info_script = matches.get_one::<String>("info-script").cloned();
+    Ok(Some(Volumes::new(
+        names,
+        info_script,
+        subcommand,
+        blocking_factor(matches),
+    )))
+}
+
+/// Whether we run as the superuser, which changes the defaults for
+/// restoring permissions and ownership on extraction.
+#[cfg(unix)]
+fn running_as_root() -> bool {
+    uucore::process::geteuid() == 0
+}
+
+#[cfg(not(unix))]
+fn running_as_root() -> bool {
+    false
+}
+
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     // Collect args - the test framework may add util_name as args[1], so skip it if present
@@ -144,7 +377,21 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     };
 
     let verbose = matches.get_flag("verbose");
+    let directories = resolve_directories(&matches);
+    let strip_components = matches
+        .get_one::<usize>("strip-components")
+        .copied()
+        .unwrap_or(0);
     let allow_absolute = matches.get_flag("absolute-names");
+    let show_transformed_names = matches.get_flag("show-transformed-names");
+    let mut transform = Transform::default();
+    for expression in matches
+        .get_many::<String>("transform")
+        .into_iter()
+        .flatten()
+    {
+        transform.add_expressions(expression)?;
+    }
     let explicit_compression = if matches.get_flag("gzip") {
         Some(CompressionMode::Gzip)
     } else if matches.get_flag("zstd") {
@@ -155,30 +402,43 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
 
     // Handle extract operation
     if matches.get_flag("extract") {
-        let archive_path = matches.get_one::<PathBuf>("file").ok_or_else(|| {
-            uucore::error::USimpleError::new(64, "option requires an argument -- 'f'")
-        })?;
+        let archive_path = archive_path(&matches)?;
 
-        let compression = explicit_compression.unwrap_or(CompressionMode::Auto);
-        return if archive_path == Path::new("-") {
-            operations::extract::extract_archive(io::stdin(), archive_path, verbose, compression)
-        } else {
-            let file =
-                File::open(archive_path).map_err(|e| TarError::from_io_error(e, archive_path))?;
-            operations::extract::extract_archive(file, archive_path, verbose, compression)
+        let options = ExtractOptions {
+            directory: directories.last().map(|(_, dir)| dir.clone()),
+            verbose,
+            compression: explicit_compression.unwrap_or(CompressionMode::Auto),
+            strip_components,
+            transform,
+            show_transformed_names,
+            old_files: old_files(&matches),
+            overwrite_dir: !matches.get_flag("no-overwrite-dir"),
+            recursive_unlink: matches.get_flag("recursive-unlink"),
+            to_stdout: matches.get_flag("to-stdout"),
+            to_command: matches.get_one::<String>("to-command").cloned(),
+            ignore_command_error: matches.get_flag("ignore-command-error"),
+            same_permissions: (running_as_root() || matches.get_flag("preserve-permissions"))
+                && !matches.get_flag("no-same-permissions"),
+            same_owner: (running_as_root() || matches.get_flag("same-owner"))
+                && !matches.get_flag("no-same-owner"),
+            numeric_owner: matches.get_flag("numeric-owner"),
+            touch: matches.get_flag("touch"),
+            delay_directory_restore: matches.get_flag("delay-directory-restore"),
+            listed_incremental: matches.contains_id("listed-incremental"),
+            label: matches.get_one::<String>("label").cloned(),
+            ignore_zeros: matches.get_flag("ignore-zeros"),
+            blocking_factor: blocking_factor(&matches),
+            progress: progress_options(&matches, "-x"),
         };
+        let input = archive_input(&matches, "-x")?;
+        return operations::extract::extract_archive(input, archive_path, &options);
     }
 
     // Handle create operation
     if matches.get_flag("create") {
-        let archive_path = matches.get_one::<PathBuf>("file").ok_or_else(|| {
-            uucore::error::USimpleError::new(64, "option requires an argument -- 'f'")
-        })?;
+        let archive_path = archive_path(&matches)?;
 
-        let files: Vec<&Path> = matches
-            .get_many::<PathBuf>("files")
-            .map(|v| v.map(|p| p.as_path()).collect())
-            .unwrap_or_default();
+        let files = file_operands(&matches, &directories);
 
         if files.is_empty() {
             return Err(uucore::error::USimpleError::new(
@@ -187,22 +447,86 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             ));
         }
 
-        let compression = explicit_compression.unwrap_or(CompressionMode::None);
+        let (mtime, clamp_mtime) = match matches.get_one::<u64>("mtime") {
+            Some(&mtime) => (Some(mtime), matches.get_flag("clamp-mtime")),
+            None => (source_date_epoch()?, true),
+        };
+        let options = CreateOptions {
+            allow_absolute,
+            verbose,
+            compression: explicit_compression.unwrap_or(CompressionMode::None),
+            transform,
+            show_transformed_names,
+            overrides: MetadataOverrides {
+                owner: matches.get_one::<IdOverride>("owner").cloned(),
+                group: matches.get_one::<IdOverride>("group").cloned(),
+                owner_map: matches
+                    .get_one::<PathBuf>("owner-map")
+                    .map(|path| read_owner_map(path))
+                    .transpose()?
+                    .unwrap_or_default(),
+                group_map: matches
+                    .get_one::<PathBuf>("group-map")
+                    .map(|path| read_group_map(path))
+                    .transpose()?
+                    .unwrap_or_default(),
+                mode: matches.get_one::<ModeChange>("mode").cloned(),
+                mtime,
+                clamp_mtime,
+                numeric_owner: matches.get_flag("numeric-owner"),
+                ..Default::default()
+            },
+            dereference: matches.get_flag("dereference"),
+            hard_dereference: matches.get_flag("hard-dereference"),
+            check_links: matches.get_flag("check-links"),
+            recursion: !matches.get_flag("no-recursion"),
+            one_file_system: matches.get_flag("one-file-system"),
+            listed_incremental: matches.get_one::<PathBuf>("listed-incremental").cloned(),
+            newer: newer_than(&matches),
+            remove_files: matches.get_flag("remove-files"),
+            label: matches.get_one::<String>("label").cloned(),
+            blocking_factor: blocking_factor(&matches),
+            sort: match matches.get_one::<String>("sort").map(String::as_str) {
+                Some("name") => SortOrder::Name,
+                Some("inode") => SortOrder::Inode,
+                _ => SortOrder::None,
+            },
+            progress: progress_options(&matches, "-c"),
+        };
         let output_is_stdout = archive_path == Path::new("-");
-        return if output_is_stdout {
+        if matches
+            .get_one::<String>("format")
+            .is_some_and(|format| format == "mtree")
+        {
+            if explicit_compression.is_some() {
+                return Err(uucore::error::USimpleError::new(
+                    2,
+                    "Cannot use compression with --format=mtree",
+                ));
+            }
+            return if output_is_stdout {
+                operations::mtree::create_spec(io::stdout(), io::stderr(), &files, &options)
+            } else {
+                let output =
+                    File::create(archive_path).map_err(|e| TarError::CannotCreateArchive {
+                        path: archive_path.clone(),
+                        source: e,
+                    })?;
+                operations::mtree::create_spec(output, io::stdout(), &files, &options)
+            };
+        }
+        return if let Some(volumes) = volumes(&matches, "-c")? {
+            let capacity = matches.get_one::<u64>("tape-length").copied();
+            let output = VolumeWriter::create(volumes, capacity)?;
+            let status_output = io::stdout().lock();
+            operations::create::create_archive(output, status_output, &files, &options)
+        } else if output_is_stdout {
             if io::stdout().is_terminal() {
                 Err(TarError::RefuseWriteArchiveToTerminal.into())
             } else {
                 let output = io::stdout().lock();
                 let status_output = io::stderr();
-                operations::create::create_archive(
-                    output,
-                    status_output,
-                    &files,
-                    allow_absolute,
-                    verbose,
-                    compression,
-                )
+                operations::create::create_archive(output, status_output, &files, &options)
             }
         } else {
             let output = File::create(archive_path).map_err(|e| TarError::CannotCreateArchive {
@@ -210,31 +534,54 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
                 source: e,
             })?;
             let status_output = io::stdout().lock();
-            operations::create::create_archive(
-                output,
-                status_output,
-                &files,
-                allow_absolute,
-                verbose,
-                compression,
-            )
+            operations::create::create_archive(output, status_output, &files, &options)
         };
     }
 
+    // Handle label test: operands are further patterns the label may match
+    if matches.get_flag("test-label") {
+        let patterns: Vec<String> = matches
+            .get_one::<String>("label")
+            .cloned()
+            .into_iter()
+            .chain(
+                file_operands(&matches, &directories)
+                    .iter()
+                    .map(|operand| operand.path.to_string_lossy().into_owned()),
+            )
+            .collect();
+        let input = archive_input(&matches, "-t")?;
+        return operations::label::test_label(
+            input,
+            &patterns,
+            explicit_compression.unwrap_or(CompressionMode::Auto),
+        );
+    }
+
     // Handle list operation
     if matches.get_flag("list") {
-        let archive_path = matches.get_one::<PathBuf>("file").ok_or_else(|| {
-            uucore::error::USimpleError::new(64, "option requires an argument -- 'f'")
-        })?;
+        let archive_path = archive_path(&matches)?;
 
-        let compression = explicit_compression.unwrap_or(CompressionMode::Auto);
-        return if archive_path == Path::new("-") {
-            operations::list::list_archive(io::stdin(), archive_path, verbose, compression)
-        } else {
-            let file =
-                File::open(archive_path).map_err(|e| TarError::from_io_error(e, archive_path))?;
-            operations::list::list_archive(file, archive_path, verbose, compression)
+        let options = ListOptions {
+            verbose,
+            compression: explicit_compression.unwrap_or(CompressionMode::Auto),
+            strip_components,
+            transform,
+            show_transformed_names,
+            label: matches.get_one::<String>("label").cloned(),
+            ignore_zeros: matches.get_flag("ignore-zeros"),
+            utc: matches.get_flag("utc"),
+            full_time: matches.get_flag("full-time"),
+            numeric_owner: matches.get_flag("numeric-owner"),
+            format: match matches.get_one::<String>("list-format").map(String::as_str) {
+                Some("json") => ListFormat::Json,
+                Some("mtree") => ListFormat::Mtree,
+                _ => ListFormat::Text,
+            },
+            progress: progress_options(&matches, "-t"),
         };
+        let input = archive_input(&matches, "-t")?;
+        return operations::list::list_archive(input, archive_path, &options);
     }
 
     // If no operation specified, show error
@@ -252,6 +599,7 @@ pub fn uu_app() -> Command {
         .override_usage(format_usage(USAGE))
         .infer_long_args(true)
         .disable_help_flag(true)
+        .disable_version_flag(true)
         .args([
             // Main operation modes
             arg!(-c --create "Create a new archive").conflicts_with_all(["extract", "list"]),
@@ -262,9 +610,146 @@ pub fn uu_app() -> Command {
             arg!(-x --extract "Extract files from archive")
                 .alias("get")
                 .conflicts_with_all(["create", "list"]),
+            arg!(--"test-label" "Test the archive volume label and exit")
+                .conflicts_with_all(["create", "extract", "list"]),
             // Archive file
             arg!(-f --file <ARCHIVE> "Use archive file or device ARCHIVE")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(PathBuf)),
+            arg!(
+                -V --label <TEXT>
+                "Create archive with volume name TEXT; when reading, TEXT is a pattern the name must match"
+            ),
+            arg!(
+                -H --format <FORMAT>
+                "Create archive of the given format: gnu (default), or mtree for a spec of the files"
+            )
+            .value_parser(["gnu", "mtree"]),
+            arg!(-b --"blocking-factor" <BLOCKS> "BLOCKS x 512 bytes per record")
+                .value_parser(parse_blocking_factor)
+                .overrides_with("record-size"),
+            arg!(--"record-size" <NUMBER> "NUMBER of bytes per record, multiple of 512")
+                .value_parser(parse_record_size)
+                .overrides_with("blocking-factor"),
+            arg!(-B --"read-full-records" "Reblock as we read (for 4.2BSD pipes)"),
+            arg!(-i --"ignore-zeros" "Ignore zeroed blocks in archive (means EOF)"),
+            arg!(--totals [SIGNAL] "Print total bytes after processing the archive, or when SIGNAL is delivered")
+                .require_equals(true)
+                .default_missing_value("")
+                .value_parser(parse_totals),
+            arg!(--checkpoint [NUMBER] "Display progress messages every NUMBER records (default 10)")
+                .require_equals(true)
+                .default_missing_value("10")
+                .value_parser(parse_checkpoint),
+            arg!(--"checkpoint-action" <ACTION> "Execute ACTION on each checkpoint: dot, echo[=STRING], bell, ttyout=STRING, exec=COMMAND, sleep=SECONDS or wait=SIGNAL")
+                .action(ArgAction::Append)
+                .value_parser(parse_checkpoint_action),
+            arg!(-M --"multi-volume" "Create/list/extract multi-volume archive"),
+            arg!(-L --"tape-length" <SIZE> "Change tape after writing SIZE bytes (units of 1024)")
+                .value_parser(parse_tape_length),
+            arg!(-F --"info-script" <SCRIPT> "Run SCRIPT at end of each tape (implies -M)")
+                .visible_alias("new-volume-script"),
+            arg!(-C --directory <DIR> "Change to directory DIR")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(PathBuf)),
+            arg!(
+                --"strip-components" <NUMBER>
+                "Strip NUMBER leading components from file names on extraction"
+            )
+            .value_parser(clap::value_parser!(usize)),
+            arg!(
+                --transform <EXPRESSION>
+                "Use sed replace EXPRESSION to transform file names"
+            )
+            .visible_alias("xform")
+            .action(ArgAction::Append),
+            arg!(
+                --"show-transformed-names"
+                "Show file or archive names after transformation"
+            ),
+            arg!(-O --"to-stdout" "Extract files to standard output"),
+            arg!(--"to-command" <COMMAND> "Pipe extracted files to another program")
+                .conflicts_with("to-stdout"),
+            arg!(--"ignore-command-error" "Ignore exit codes of children"),
+            // Metadata stored on creation
+            arg!(--owner <NAME> "Force NAME as owner for added files")
+                .value_parser(parse_owner),
+            arg!(--group <NAME> "Force NAME as group for added files")
+                .value_parser(parse_group),
+            arg!(--"owner-map" <FILE> "Use FILE to map file owner UIDs and names")
                 .value_parser(clap::value_parser!(PathBuf)),
+            arg!(--"group-map" <FILE> "Use FILE to map file owner GIDs and names")
+                .value_parser(clap::value_parser!(PathBuf)),
+            arg!(--mode <CHANGES> "Force (symbolic) mode CHANGES for added files")
+                .value_parser(parse_mode),
+            arg!(--mtime <DATE_OR_FILE> "Set mtime for added files from DATE_OR_FILE")
+                .value_parser(parse_mtime),
+            arg!(
+                --"clamp-mtime"
+                "Only set time when the file is more recent than what was given with --mtime"
+            )
+            .requires("mtime"),
+            arg!(--sort <ORDER> "Directory sorting order: none (default), name or inode")
+                .value_parser(["none", "name", "inode"]),
+            arg!(--"pax-option" <LIST> "Control pax keywords (only delete=PATTERN is accepted)")
+                .value_parser(parse_pax_option)
+                .action(ArgAction::Append),
+            // Metadata restored on extraction
+            arg!(
+                -p --"preserve-permissions"
+                "Extract information about file permissions (default for superuser)"
+            )
+            .visible_alias("same-permissions")
+            .overrides_with("no-same-permissions"),
+            arg!(
+                --"no-same-permissions"
+                "Apply the user's umask when extracting permissions from the archive (default for ordinary users)"
+            )
+            .overrides_with("preserve-permissions"),
+            arg!(
+                --"same-owner"
+                "Try extracting files with the same ownership as exists in the archive (default for superuser)"
+            )
+            .overrides_with("no-same-owner"),
+            arg!(
+                -o --"no-same-owner"
+                "Extract files as yourself (default for ordinary users)"
+            )
+            .overrides_with("same-owner"),
+            arg!(--"numeric-owner" "Always use numbers for user/group names"),
+            arg!(-m --touch "Don't extract file modified time"),
+            arg!(
+                --"delay-directory-restore"
+                "Delay setting modification times and permissions of extracted directories until the end of extraction"
+            )
+            .overrides_with("no-delay-directory-restore"),
+            arg!(
+                --"no-delay-directory-restore"
+                "Cancel the effect of --delay-directory-restore option"
+            )
+            .overrides_with("delay-directory-restore"),
+            // Overwrite control
+            arg!(-k --"keep-old-files" "Don't replace existing files when extracting")
+                .overrides_with_all(OLD_FILES_OPTIONS),
+            arg!(--"skip-old-files" "Don't replace existing files, silently skip them")
+                .overrides_with_all(OLD_FILES_OPTIONS),
+            arg!(
+                --"keep-newer-files"
+                "Don't replace existing files that are newer than their archive copies"
+            )
+            .overrides_with_all(OLD_FILES_OPTIONS),
+            arg!(--overwrite "Overwrite existing files when extracting")
+                .overrides_with_all(OLD_FILES_OPTIONS),
+            arg!(-U --"unlink-first" "Remove each file prior to extracting over it")
+                .overrides_with_all(OLD_FILES_OPTIONS),
+            arg!(--"overwrite-dir" "Overwrite metadata of existing directories when extracting")
+                .overrides_with("no-overwrite-dir"),
+            arg!(--"no-overwrite-dir" "Preserve metadata of existing directories")
+                .overrides_with("overwrite-dir"),
+            arg!(
+                --"recursive-unlink"
+                "Empty hierarchies prior to extracting directory"
+            ),
             arg!(
                 -P --"absolute-names"
                 "Don't strip leading '/'s from file names"
@@ -275,11 +760,38 @@ pub fn uu_app() -> Command {
             // arg!(-J --xz "Filter through xz"),
             arg!(--zstd "Filter through zstd").conflicts_with("gzip"),
             // Common options
-            arg!(-v --verbose "Verbosely list files processed"),
-            // arg!(-h --dereference "Follow symlinks"),
-            // arg!(-p --"preserve-permissions" "Extract information about file permissions"),
+            // Repeating it (-tvv) lists the same way
+            arg!(-v --verbose "Verbosely list files processed").overrides_with("verbose"),
+            arg!(--utc "Print file modification times in UTC"),
+            arg!(--"full-time" "Print file time to its full resolution"),
+            arg!(--"list-format" <FORMAT> "Listing format: text (default), json (one object per line) or mtree")
+                .value_parser(["text", "json", "mtree"]),
+            arg!(
+                -h --dereference
+                "Follow symlinks; archive and dump the files they point to"
+            ),
+            arg!(
+                --"hard-dereference"
+                "Follow hard links; archive and dump the files they refer to"
+            ),
+            arg!(-l --"check-links" "Print a message if not all links are dumped"),
+            arg!(--"one-file-system" "Stay in local file system when creating archive"),
+            arg!(-N --newer <DATE_OR_FILE> "Only store files newer than DATE_OR_FILE")
+                .visible_alias("after-date")
+                .value_parser(parse_mtime)
+                .overrides_with("newer-mtime"),
+            arg!(--"newer-mtime" <DATE> "Compare date and time when data changed only")
+                .value_parser(parse_mtime)
+                .overrides_with("newer"),
+            arg!(--"remove-files" "Remove files after adding them to the archive"),
+            arg!(-g --"listed-incremental" <FILE> "Handle new GNU-format incremental backup")
+                .value_parser(clap::value_parser!(PathBuf)),
+            arg!(--"no-recursion" "Avoid descending automatically in directories")
+                .overrides_with("recursion"),
+            arg!(--recursion "Recurse into directories (default)").overrides_with("no-recursion"),
             // Help
             arg!(--help "Print help information").action(ArgAction::Help),
+            arg!(--version "Print version information").action(ArgAction::Version),
             // Files to process
             arg!([files]... "Files to archive or extract")
                 .action(ArgAction::Append)
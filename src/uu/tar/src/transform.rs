@@ -0,0 +1,566 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Member name transformations (`--transform`/`--xform`).
+//!
+//! Expressions use the `sed` substitution syntax accepted by GNU tar:
+//! `s/REGEX/REPLACEMENT/FLAGS`, where any character may replace `/` as the
+//! delimiter and several expressions may be joined with `;`.  REGEX is a
+//! POSIX basic regular expression, or an extended one with the `x` flag.
+
+use crate::errors::TarError;
+use regex::bytes::{Captures, Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// The kind of name a transformation is being applied to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransformScope {
+    /// Member names (`r` flag)
+    Regular,
+    /// Symbolic link targets (`s` flag)
+    Symlink,
+    /// Hard link targets (`h` flag)
+    Hardlink,
+}
+
+/// An ordered list of substitutions applied to member names.
+#[derive(Debug, Default)]
+pub struct Transform {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    regex: Regex,
+    replacement: Vec<Replacement>,
+    global: bool,
+    occurrence: usize,
+    regular: bool,
+    symlink: bool,
+    hardlink: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Replacement {
+    Literal(Vec<u8>),
+    Group(usize),
+    Case(CaseChange),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CaseChange {
+    Upper,
+    Lower,
+    UpperNext,
+    LowerNext,
+    End,
+}
+
+impl Transform {
+    /// Parse one `--transform` argument, which may hold several
+    /// `;`-separated expressions, and append its rules.
+    pub fn add_expressions(&mut self, expressions: &str) -> Result<(), TarError> {
+        let mut rest = expressions;
+        while !rest.is_empty() {
+            let (rule, remainder) = parse_rule(rest)?;
+            self.rules.push(rule);
+            rest = remainder;
+        }
+        Ok(())
+    }
+
+    /// Returns true if no expressions were given.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply every rule whose scope includes `scope` to `name`, in order.
+    pub fn apply<'a>(&self, name: &'a [u8], scope: TransformScope) -> Cow<'a, [u8]> {
+        let mut current = Cow::Borrowed(name);
+        for rule in &self.rules {
+            if rule.applies_to(scope) {
+                if let Cow::Owned(replaced) = rule.apply(&current) {
+                    current = Cow::Owned(replaced);
+                }
+            }
+        }
+        current
+    }
+
+    /// Apply the transformation to a path.
+    ///
+    /// Returns `None` if the transformed name is empty, in which case the
+    /// member should be skipped.
+    pub fn apply_path(&self, path: &Path, scope: TransformScope) -> Option<PathBuf> {
+        if self.is_empty() {
+            return Some(path.to_path_buf());
+        }
+        let transformed = self.apply(&path_to_bytes(path), scope).into_owned();
+        if transformed.is_empty() {
+            None
+        } else {
+            Some(bytes_to_path(transformed))
+        }
+    }
+}
+
+impl Rule {
+    fn applies_to(&self, scope: TransformScope) -> bool {
+        match scope {
+            TransformScope::Regular => self.regular,
+            TransformScope::Symlink => self.symlink,
+            TransformScope::Hardlink => self.hardlink,
+        }
+    }
+
+    fn apply<'a>(&self, subject: &'a [u8]) -> Cow<'a, [u8]> {
+        let mut result = Vec::new();
+        let mut last = 0;
+        let mut replaced = false;
+        for (index, caps) in self.regex.captures_iter(subject).enumerate() {
+            let nth = index + 1;
+            if nth < self.occurrence {
+                continue;
+            }
+            if nth > self.occurrence && !self.global {
+                break;
+            }
+            let whole = caps.get(0).unwrap();
+            result.extend_from_slice(&subject[last..whole.start()]);
+            self.expand(&caps, &mut result);
+            last = whole.end();
+            replaced = true;
+        }
+        if !replaced {
+            return Cow::Borrowed(subject);
+        }
+        result.extend_from_slice(&subject[last..]);
+        Cow::Owned(result)
+    }
+
+    fn expand(&self, caps: &Captures<'_>, out: &mut Vec<u8>) {
+        let mut mode = None;
+        let mut next = None;
+        for part in &self.replacement {
+            let text: &[u8] = match part {
+                Replacement::Literal(bytes) => bytes,
+                Replacement::Group(n) => caps.get(*n).map_or(&[][..], |m| m.as_bytes()),
+                Replacement::Case(CaseChange::Upper) => {
+                    mode = Some(CaseChange::Upper);
+                    continue;
+                }
+                Replacement::Case(CaseChange::Lower) => {
+                    mode = Some(CaseChange::Lower);
+                    continue;
+                }
+                Replacement::Case(CaseChange::End) => {
+                    mode = None;
+                    continue;
+                }
+                Replacement::Case(change) => {
+                    next = Some(*change);
+                    continue;
+                }
+            };
+            if text.is_empty() {
+                continue;
+            }
+            let converted = match mode {
+                Some(CaseChange::Upper) => change_case(text, true),
+                Some(CaseChange::Lower) => change_case(text, false),
+                _ => text.to_vec(),
+            };
+            match next.take() {
+                Some(change) => {
+                    let first_len = std::str::from_utf8(&converted)
+                        .ok()
+                        .and_then(|s| s.chars().next())
+                        .map_or(1, char::len_utf8);
+                    let (first, rest) = converted.split_at(first_len);
+                    out.extend(change_case(first, change == CaseChange::UpperNext));
+                    out.extend_from_slice(rest);
+                }
+                None => out.extend(converted),
+            }
+        }
+    }
+}
+
+fn change_case(text: &[u8], upper: bool) -> Vec<u8> {
+    match (std::str::from_utf8(text), upper) {
+        (Ok(s), true) => s.to_uppercase().into_bytes(),
+        (Ok(s), false) => s.to_lowercase().into_bytes(),
+        (Err(_), true) => text.to_ascii_uppercase(),
+        (Err(_), false) => text.to_ascii_lowercase(),
+    }
+}
+
+fn invalid(expression: &str, reason: &str) -> TarError {
+    TarError::InvalidTransform(format!("{reason} in transform expression '{expression}'"))
+}
+
+/// Parse a single expression from the start of `input`, returning the rule
+/// and whatever follows the terminating `;`.
+fn parse_rule(input: &str) -> Result<(Rule, &str), TarError> {
+    let mut chars = input.char_indices();
+    match chars.next() {
+        Some((_, 's')) => {}
+        _ => return Err(invalid(input, "Invalid transform expression")),
+    }
+    let Some((_, delimiter)) = chars.next() else {
+        return Err(invalid(input, "Missing delimiter"));
+    };
+    if delimiter == '\\' || delimiter == '\n' {
+        return Err(invalid(input, "Invalid delimiter"));
+    }
+
+    let body = &input[1 + delimiter.len_utf8()..];
+    let (pattern, body) = split_at_delimiter(body, delimiter)
+        .ok_or_else(|| invalid(input, "Missing 2nd delimiter"))?;
+    let (replacement, body) = split_at_delimiter(body, delimiter)
+        .ok_or_else(|| invalid(input, "Missing 3rd delimiter"))?;
+
+    let mut rule_flags = Flags::default();
+    let mut rest = "";
+    let mut occurrence = String::new();
+    for (i, c) in body.char_indices() {
+        match c {
+            ';' => {
+                rest = &body[i + 1..];
+                break;
+            }
+            'g' => rule_flags.global = true,
+            'i' => rule_flags.ignore_case = true,
+            'x' => rule_flags.extended = true,
+            'r' => rule_flags.regular = true,
+            'R' => rule_flags.regular = false,
+            's' => rule_flags.symlink = true,
+            'S' => rule_flags.symlink = false,
+            'h' => rule_flags.hardlink = true,
+            'H' => rule_flags.hardlink = false,
+            '0'..='9' => occurrence.push(c),
+            other => {
+                return Err(invalid(input, &format!("Unknown flag '{other}'")));
+            }
+        }
+    }
+    let occurrence = if occurrence.is_empty() {
+        1
+    } else {
+        match occurrence.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(invalid(input, "Invalid occurrence number")),
+        }
+    };
+
+    let translated =
+        translate_regex(&pattern, rule_flags.extended).map_err(|reason| invalid(input, &reason))?;
+    let regex = RegexBuilder::new(&translated)
+        .case_insensitive(rule_flags.ignore_case)
+        .build()
+        .map_err(|e| invalid(input, &format!("Invalid regular expression ({e})")))?;
+    let replacement = parse_replacement(&replacement, regex.captures_len())
+        .map_err(|reason| invalid(input, &reason))?;
+
+    Ok((
+        Rule {
+            regex,
+            replacement,
+            global: rule_flags.global,
+            occurrence,
+            regular: rule_flags.regular,
+            symlink: rule_flags.symlink,
+            hardlink: rule_flags.hardlink,
+        },
+        rest,
+    ))
+}
+
+struct Flags {
+    global: bool,
+    ignore_case: bool,
+    extended: bool,
+    regular: bool,
+    symlink: bool,
+    hardlink: bool,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Self {
+            global: false,
+            ignore_case: false,
+            extended: false,
+            regular: true,
+            symlink: true,
+            hardlink: true,
+        }
+    }
+}
+
+/// Split `input` at the first unescaped `delimiter`.
+///
+/// An escaped delimiter is replaced by a private marker (`\` followed by
+/// NUL) so the regex and replacement parsers can treat it as a literal.
+fn split_at_delimiter(input: &str, delimiter: char) -> Option<(String, &str)> {
+    let mut part = String::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == delimiter {
+            return Some((part, &input[i + c.len_utf8()..]));
+        }
+        if c == '\\' {
+            match chars.next() {
+                Some((_, next)) if next == delimiter => {
+                    part.push('\\');
+                    part.push('\0');
+                    part.push(delimiter);
+                }
+                Some((_, next)) => {
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => part.push('\\'),
+            }
+        } else {
+            part.push(c);
+        }
+    }
+    None
+}
+
+/// Translate a POSIX basic (or, with `extended`, extended) regular
+/// expression into the syntax understood by the `regex` crate.
+fn translate_regex(pattern: &str, extended: bool) -> Result<String, String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::new();
+    // Whether the next character starts a (sub)expression, where a `*` is
+    // literal in a basic regular expression.
+    let mut at_start = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let starts_expression = match c {
+            '\\' => {
+                let Some(&next) = chars.get(i + 1) else {
+                    return Err("Trailing backslash".to_string());
+                };
+                i += 1;
+                match next {
+                    '\0' => {
+                        // Escaped delimiter, always a literal
+                        i += 1;
+                        out.push_str(&regex::escape(&chars[i].to_string()));
+                        false
+                    }
+                    '(' | '|' if !extended => {
+                        out.push(next);
+                        true
+                    }
+                    ')' | '{' | '}' | '+' | '?' if !extended => {
+                        out.push(next);
+                        false
+                    }
+                    '1'..='9' => {
+                        return Err("Back-references are not supported".to_string());
+                    }
+                    'n' => {
+                        out.push_str("\\n");
+                        false
+                    }
+                    't' => {
+                        out.push_str("\\t");
+                        false
+                    }
+                    '`' => {
+                        out.push_str("\\A");
+                        false
+                    }
+                    '\'' => {
+                        out.push_str("\\z");
+                        false
+                    }
+                    'w' | 'W' | 's' | 'S' | 'b' | 'B' | '<' | '>' => {
+                        out.push('\\');
+                        out.push(next);
+                        false
+                    }
+                    other => {
+                        out.push_str(&regex::escape(&other.to_string()));
+                        false
+                    }
+                }
+            }
+            '[' => {
+                i = translate_bracket(&chars, i, &mut out)?;
+                false
+            }
+            '(' | '|' if extended => {
+                out.push(c);
+                true
+            }
+            ')' | '{' | '}' | '+' | '?' if extended => {
+                out.push(c);
+                false
+            }
+            '*' if at_start && !extended => {
+                out.push_str("\\*");
+                false
+            }
+            '*' | '.' => {
+                out.push(c);
+                false
+            }
+            '^' if extended || at_start => {
+                out.push('^');
+                true
+            }
+            '$' if extended
+                || i + 1 == chars.len()
+                || (chars[i + 1] == '\\' && chars.get(i + 2) == Some(&')')) =>
+            {
+                out.push('$');
+                false
+            }
+            other => {
+                out.push_str(&regex::escape(&other.to_string()));
+                false
+            }
+        };
+        at_start = starts_expression;
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// Translate the bracket expression starting at `chars[start]` (a `[`),
+/// returning the index of its closing `]`.
+fn translate_bracket(chars: &[char], start: usize, out: &mut String) -> Result<usize, String> {
+    let mut i = start + 1;
+    out.push('[');
+    if chars.get(i) == Some(&'^') {
+        out.push('^');
+        i += 1;
+    }
+    // A `]` right after the opening bracket is a literal
+    if chars.get(i) == Some(&']') {
+        out.push_str("\\]");
+        i += 1;
+    }
+    while let Some(&c) = chars.get(i) {
+        match c {
+            ']' => {
+                out.push(']');
+                return Ok(i);
+            }
+            '[' if matches!(chars.get(i + 1), Some(':' | '.' | '=')) => {
+                // Character class such as [:alpha:], copied through verbatim
+                let kind = chars[i + 1];
+                let end = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == kind && chars[j + 1] == ']')
+                    .ok_or_else(|| "Unterminated character class".to_string())?;
+                out.extend(&chars[i..end + 2]);
+                i = end + 1;
+            }
+            '\\' if chars.get(i + 1) == Some(&'\0') => {
+                // Escaped delimiter
+                i += 2;
+                out.push_str(&regex::escape(&chars[i].to_string()));
+            }
+            // Characters that are literal in POSIX brackets but have a
+            // meaning inside `regex` crate classes
+            '\\' | '[' | '&' | '~' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    Err("Unterminated bracket expression".to_string())
+}
+
+fn parse_replacement(replacement: &str, groups: usize) -> Result<Vec<Replacement>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = replacement.chars();
+    let flush = |literal: &mut String, parts: &mut Vec<Replacement>| {
+        if !literal.is_empty() {
+            parts.push(Replacement::Literal(std::mem::take(literal).into_bytes()));
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => {
+                flush(&mut literal, &mut parts);
+                parts.push(Replacement::Group(0));
+            }
+            '\\' => {
+                let Some(next) = chars.next() else {
+                    literal.push('\\');
+                    continue;
+                };
+                let part = match next {
+                    '0'..='9' => {
+                        let n = next.to_digit(10).unwrap() as usize;
+                        if n >= groups {
+                            return Err(format!("Invalid reference \\{n} on replacement"));
+                        }
+                        Replacement::Group(n)
+                    }
+                    'U' => Replacement::Case(CaseChange::Upper),
+                    'L' => Replacement::Case(CaseChange::Lower),
+                    'u' => Replacement::Case(CaseChange::UpperNext),
+                    'l' => Replacement::Case(CaseChange::LowerNext),
+                    'E' => Replacement::Case(CaseChange::End),
+                    'n' => {
+                        literal.push('\n');
+                        continue;
+                    }
+                    '\0' => continue,
+                    other => {
+                        literal.push(other);
+                        continue;
+                    }
+                };
+                flush(&mut literal, &mut parts);
+                parts.push(part);
+            }
+            other => literal.push(other),
+        }
+    }
+    flush(&mut literal, &mut parts);
+    Ok(parts)
+}
+
+#[cfg(unix)]
+pub(crate) fn path_to_bytes(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn path_to_bytes(path: &Path) -> Cow<'_, [u8]> {
+    match path.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+#[path = "transform_tests.rs"]
+mod tests;
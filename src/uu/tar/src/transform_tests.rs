@@ -0,0 +1,134 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+
+fn transform(expressions: &[&str]) -> Transform {
+    let mut transform = Transform::default();
+    for expression in expressions {
+        transform.add_expressions(expression).unwrap();
+    }
+    transform
+}
+
+fn apply(expressions: &[&str], name: &str) -> String {
+    let result = transform(expressions).apply(name.as_bytes(), TransformScope::Regular);
+    String::from_utf8(result.into_owned()).unwrap()
+}
+
+#[test]
+fn test_simple_substitution() {
+    assert_eq!(
+        apply(&["s,^build/,pkg/usr/,"], "build/bin/tool"),
+        "pkg/usr/bin/tool"
+    );
+    assert_eq!(
+        apply(&["s,^build/,pkg/usr/,"], "src/build/x"),
+        "src/build/x"
+    );
+}
+
+#[test]
+fn test_first_occurrence_only_by_default() {
+    assert_eq!(apply(&["s/a/b/"], "aaa"), "baa");
+}
+
+#[test]
+fn test_global_flag() {
+    assert_eq!(apply(&["s/a/b/g"], "aaa"), "bbb");
+}
+
+#[test]
+fn test_numeric_occurrence() {
+    assert_eq!(apply(&["s/a/b/2"], "aaaa"), "abaa");
+    assert_eq!(apply(&["s/a/b/2g"], "aaaa"), "abbb");
+}
+
+#[test]
+fn test_ignore_case_flag() {
+    assert_eq!(apply(&["s/readme/README/i"], "docs/ReadMe"), "docs/README");
+}
+
+#[test]
+fn test_basic_regex_groups_and_backreferences() {
+    assert_eq!(
+        apply(&[r"s/\([a-z]*\)-\([0-9.]*\)/\2-\1/"], "project-1.2.3"),
+        "1.2.3-project"
+    );
+    // Parentheses and `+` are literals in a basic regular expression
+    assert_eq!(apply(&["s/(a+)/x/"], "b(a+)c"), "bxc");
+}
+
+#[test]
+fn test_extended_regex_flag() {
+    assert_eq!(
+        apply(&["s/([a-z]+)-([0-9.]+)/\\2-\\1/x"], "project-1.2.3"),
+        "1.2.3-project"
+    );
+}
+
+#[test]
+fn test_ampersand_and_escapes_in_replacement() {
+    assert_eq!(apply(&["s/file/&.bak/"], "file"), "file.bak");
+    assert_eq!(apply(&[r"s/file/a\&b/"], "file"), "a&b");
+}
+
+#[test]
+fn test_case_conversion_in_replacement() {
+    assert_eq!(apply(&[r"s/.*/\U&/"], "name"), "NAME");
+    assert_eq!(apply(&[r"s/\(.\)\(.*\)/\u\1\2/"], "name"), "Name");
+    assert_eq!(apply(&[r"s/\(.*\)-\(.*\)/\U\1\E-\2/"], "ab-cd"), "AB-cd");
+}
+
+#[test]
+fn test_escaped_delimiter() {
+    assert_eq!(apply(&[r"s/a\/b/c/"], "a/b"), "c");
+    assert_eq!(apply(&[r"s,x,a\,b,"], "x"), "a,b");
+}
+
+#[test]
+fn test_multiple_expressions_are_applied_in_order() {
+    assert_eq!(apply(&["s/a/b/;s/b/c/"], "a"), "c");
+    assert_eq!(apply(&["s/a/b/", "s/b/c/"], "a"), "c");
+}
+
+#[test]
+fn test_bracket_expressions() {
+    assert_eq!(apply(&["s/[[:digit:]]\\{1,\\}/N/g"], "v12.3"), "vN.N");
+    assert_eq!(apply(&["s/[]x]/_/g"], "a]x"), "a__");
+}
+
+#[test]
+fn test_scope_flags() {
+    let t = transform(&["s/old/new/S"]);
+    assert_eq!(t.apply(b"old", TransformScope::Regular).as_ref(), b"new");
+    assert_eq!(t.apply(b"old", TransformScope::Symlink).as_ref(), b"old");
+    assert_eq!(t.apply(b"old", TransformScope::Hardlink).as_ref(), b"new");
+
+    let t = transform(&["s/old/new/RH"]);
+    assert_eq!(t.apply(b"old", TransformScope::Regular).as_ref(), b"old");
+    assert_eq!(t.apply(b"old", TransformScope::Symlink).as_ref(), b"new");
+    assert_eq!(t.apply(b"old", TransformScope::Hardlink).as_ref(), b"old");
+}
+
+#[test]
+fn test_apply_path_skips_empty_names() {
+    let t = transform(&["s/.*//"]);
+    assert_eq!(
+        t.apply_path(Path::new("dir"), TransformScope::Regular),
+        None
+    );
+}
+
+#[test]
+fn test_invalid_expressions() {
+    let mut t = Transform::default();
+    assert!(t.add_expressions("y/a/b/").is_err());
+    assert!(t.add_expressions("s/a/b").is_err());
+    assert!(t.add_expressions("s/a/b/q").is_err());
+    assert!(t.add_expressions(r"s/a/\1/").is_err());
+    assert!(t.add_expressions("s/[a/b/").is_err());
+    assert!(t.is_empty());
+}
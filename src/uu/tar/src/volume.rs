@@ -0,0 +1,503 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Multi-volume archives (`-M`): the block stream of one archive split
+//! across several files of limited size (`-L`), with GNU continuation
+//! headers for members that straddle two volumes.
+
+use crate::errors::TarError;
+use crate::record::{parse_size, BLOCK_SIZE};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tar::{EntryType, Header};
+
+/// Type flag of GNU continuation headers
+pub(crate) const CONTINUATION: u8 = b'M';
+
+/// Bytes of a header holding the offset of an `M` header's data
+const OFFSET_FIELD: std::ops::Range<usize> = 369..381;
+
+/// Parse `--tape-length=SIZE`: a number of 1024-byte units, or of bytes
+/// with a `b` (512), `K`, `M`, `G` or `T` suffix.  The result is in bytes,
+/// rounded down to whole blocks.
+pub fn parse_tape_length(value: &str) -> Result<u64, String> {
+    let bytes = parse_size(value, 1024)
+        .map(|bytes| bytes - bytes % BLOCK_SIZE as u64)
+        .filter(|&bytes| bytes >= 2 * BLOCK_SIZE as u64)
+        .ok_or_else(|| format!("Invalid tape length '{value}'"))?;
+    Ok(bytes)
+}
+
+/// The files making up a multi-volume archive, and how to move between them
+#[derive(Debug)]
+pub struct Volumes {
+    /// Names given with `-f`, used in turn
+    names: Vec<PathBuf>,
+    /// Run before each volume after the first (`-F`)
+    info_script: Option<String>,
+    /// `-c`, `-x` or `-t`, passed to the script as `TAR_SUBCOMMAND`
+    subcommand: &'static str,
+    /// Passed to the script as `TAR_BLOCKING_FACTOR`
+    blocking_factor: usize,
+    /// Number of the current volume, from 1
+    number: usize,
+}
+
+impl Volumes {
+    pub fn new(
+        names: Vec<PathBuf>,
+        info_script: Option<String>,
+        subcommand: &'static str,
+        blocking_factor: usize,
+    ) -> Self {
+        Self {
+            names,
+            info_script,
+            subcommand,
+            blocking_factor,
+            number: 1,
+        }
+    }
+
+    /// File name of the current volume
+    fn path(&self) -> &Path {
+        &self.names[(self.number - 1) % self.names.len()]
+    }
+
+    /// Whether another volume can be moved to: each needs its own `-f`
+    /// name unless an info script is there to prepare it.
+    fn has_next(&self) -> bool {
+        self.info_script.is_some() || self.number < self.names.len()
+    }
+
+    /// Move on to the next volume, running the info script first if there
+    /// is one, and return its file name.
+    fn advance(&mut self) -> io::Result<PathBuf> {
+        if !self.has_next() {
+            return Err(io::Error::other(format!(
+                "No archive name for volume #{}; give another -f or an -F script",
+                self.number + 1
+            )));
+        }
+        self.number += 1;
+        if let Some(script) = &self.info_script {
+            let status = Command::new("sh")
+                .arg("-c")
+                .arg(script)
+                .env("TAR_VERSION", env!("CARGO_PKG_VERSION"))
+                .env("TAR_ARCHIVE", self.path())
+                .env("TAR_VOLUME", self.number.to_string())
+                .env("TAR_BLOCKING_FACTOR", self.blocking_factor.to_string())
+                .env("TAR_SUBCOMMAND", self.subcommand)
+                .env("TAR_FORMAT", "gnu")
+                .status()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("'{script}' command failed")));
+            }
+        }
+        Ok(self.path().to_path_buf())
+    }
+}
+
+/// What the data blocks that follow a header belong to
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum Data {
+    /// A member's contents
+    #[default]
+    Member,
+    /// A GNU long name, naming the next member
+    LongName,
+    /// Other records that describe the next member, such as long link
+    /// names and pax headers
+    Extension,
+}
+
+/// Follows a stream of blocks to tell headers from member data, and which
+/// member the data belongs to
+#[derive(Debug, Default)]
+struct MemberTracker {
+    /// Name of the member whose data is being passed through
+    name: Vec<u8>,
+    /// Size of that member's data
+    size: u64,
+    /// Bytes of that member's data seen so far
+    offset: u64,
+    /// Data blocks still to come after the last header
+    data_blocks: u64,
+    data: Data,
+    /// A long name read for the next member
+    long_name: Option<Vec<u8>>,
+    /// Whether the end-of-archive marker has been seen; all that follows
+    /// is padding
+    ended: bool,
+}
+
+impl MemberTracker {
+    fn in_data(&self) -> bool {
+        self.data_blocks > 0
+    }
+
+    /// Whether the stream is in the middle of a member's contents, which
+    /// a continuation header can resume.
+    fn in_member_data(&self) -> bool {
+        self.in_data() && self.data == Data::Member
+    }
+
+    fn advance(&mut self, block: &[u8; BLOCK_SIZE]) {
+        if self.in_data() {
+            self.data_blocks -= 1;
+            match self.data {
+                Data::Member => self.offset += BLOCK_SIZE as u64,
+                Data::LongName => self.long_name.get_or_insert_default().extend(block),
+                Data::Extension => {}
+            }
+            return;
+        }
+        if is_zero(block) {
+            self.ended = true;
+            return;
+        }
+
+        let header = Header::from_byte_slice(block);
+        let size = header.entry_size().unwrap_or(0);
+        self.data_blocks = size.div_ceil(BLOCK_SIZE as u64);
+        self.data = extension_kind(header);
+        match self.data {
+            Data::LongName => self.long_name = Some(Vec::new()),
+            Data::Extension => {}
+            Data::Member => {
+                self.name = match self.long_name.take() {
+                    Some(mut name) => {
+                        name.truncate(name.iter().position(|&b| b == 0).unwrap_or(name.len()));
+                        name
+                    }
+                    None => header.path_bytes().into_owned(),
+                };
+                self.size = size;
+                self.offset = 0;
+            }
+        }
+    }
+
+    /// Bytes of the current member's data still to come
+    fn remaining(&self) -> u64 {
+        self.size.saturating_sub(self.offset)
+    }
+}
+
+fn is_zero(block: &[u8]) -> bool {
+    block.iter().all(|&b| b == 0)
+}
+
+fn extension_kind(header: &Header) -> Data {
+    let entry_type = header.entry_type();
+    if entry_type.is_gnu_longname() {
+        Data::LongName
+    } else if entry_type.is_gnu_longlink()
+        || entry_type.is_pax_local_extensions()
+        || entry_type.is_pax_global_extensions()
+    {
+        Data::Extension
+    } else {
+        Data::Member
+    }
+}
+
+/// Number of blocks in the record a header starts, if it must not be split
+/// across volumes: extension records are only ever kept whole.
+fn unsplittable_blocks(block: &[u8; BLOCK_SIZE]) -> u64 {
+    if is_zero(block) {
+        return 1;
+    }
+    let header = Header::from_byte_slice(block);
+    match extension_kind(header) {
+        Data::Member => 1,
+        _ => 1 + header.entry_size().unwrap_or(0).div_ceil(BLOCK_SIZE as u64),
+    }
+}
+
+/// Header blocks that resume the member `tracker` is in on a new volume:
+/// a GNU long name if needed, then an `M` header giving the offset.
+fn continuation_headers(tracker: &MemberTracker) -> Vec<u8> {
+    let mut blocks = Vec::new();
+    let name = &tracker.name;
+    let mut header = Header::new_gnu();
+    if name.len() > 100 {
+        let mut long_name = Header::new_gnu();
+        long_name.as_gnu_mut().unwrap().name[..13].copy_from_slice(b"././@LongLink");
+        long_name.set_mode(0o644);
+        long_name.set_uid(0);
+        long_name.set_gid(0);
+        long_name.set_mtime(0);
+        long_name.set_size(name.len() as u64 + 1);
+        long_name.set_entry_type(EntryType::GNULongName);
+        long_name.set_cksum();
+        blocks.extend_from_slice(long_name.as_bytes());
+        blocks.extend_from_slice(name);
+        blocks.push(0);
+        blocks.resize(blocks.len().next_multiple_of(BLOCK_SIZE), 0);
+    }
+    let stored = name.len().min(100);
+    let gnu = header.as_gnu_mut().unwrap();
+    gnu.name[..stored].copy_from_slice(&name[..stored]);
+    gnu.offset = octal_field(tracker.offset);
+    header.set_mode(0o644);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_mtime(0);
+    header.set_size(tracker.remaining());
+    header.set_entry_type(EntryType::new(CONTINUATION));
+    header.set_cksum();
+    blocks.extend_from_slice(header.as_bytes());
+    blocks
+}
+
+/// Encode the 12-byte numeric field of an `M` header's offset.
+fn octal_field(value: u64) -> [u8; 12] {
+    let mut field = [0; 12];
+    field[..11].copy_from_slice(format!("{value:011o}").as_bytes());
+    field
+}
+
+/// Where in its member the data after an `M` header resumes.  GNU tar
+/// leaves the magic of these headers empty, so the offset field is read
+/// from its place in the block rather than through a GNU header view.
+pub(crate) fn continued_at(header: &Header) -> Option<u64> {
+    parse_octal(&header.as_bytes()[OFFSET_FIELD])
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let digits = std::str::from_utf8(field).ok()?;
+    u64::from_str_radix(digits.trim_matches(|c: char| c == '\0' || c == ' '), 8).ok()
+}
+
+/// Writes an archive across volumes of at most `capacity` bytes each
+#[derive(Debug)]
+pub struct VolumeWriter {
+    volumes: Volumes,
+    file: BufWriter<File>,
+    capacity: u64,
+    /// Bytes written to the current volume
+    written: u64,
+    tracker: MemberTracker,
+    /// Start of a block not yet complete
+    pending: Vec<u8>,
+}
+
+impl VolumeWriter {
+    /// Create the first volume.  Without a `capacity`, volumes are only
+    /// limited by the space on the device.
+    pub fn create(volumes: Volumes, capacity: Option<u64>) -> Result<Self, TarError> {
+        let path = volumes.path();
+        let file = File::create(path).map_err(|e| TarError::CannotCreateArchive {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        Ok(Self {
+            volumes,
+            file: BufWriter::new(file),
+            capacity: capacity.unwrap_or(u64::MAX),
+            written: 0,
+            tracker: MemberTracker::default(),
+            pending: Vec::with_capacity(BLOCK_SIZE),
+        })
+    }
+
+    fn free_blocks(&self) -> u64 {
+        (self.capacity - self.written) / BLOCK_SIZE as u64
+    }
+
+    fn write_block(&mut self, block: &[u8; BLOCK_SIZE]) -> io::Result<()> {
+        if self.tracker.in_data() {
+            if self.free_blocks() == 0 {
+                if !self.tracker.in_member_data() {
+                    return Err(volume_too_small());
+                }
+                self.next_volume()?;
+                let headers = continuation_headers(&self.tracker);
+                if headers.len() as u64 + BLOCK_SIZE as u64 > self.capacity {
+                    return Err(volume_too_small());
+                }
+                self.file.write_all(&headers)?;
+                self.written += headers.len() as u64;
+            }
+        } else if self.tracker.ended && self.free_blocks() == 0 {
+            // Padding past the end of the archive needs no new volume
+            return Ok(());
+        } else {
+            let needed = unsplittable_blocks(block);
+            if needed > self.free_blocks() && self.written > 0 {
+                self.next_volume()?;
+            }
+            if needed > self.free_blocks() {
+                return Err(volume_too_small());
+            }
+        }
+
+        self.file.write_all(block)?;
+        self.written += BLOCK_SIZE as u64;
+        self.tracker.advance(block);
+        Ok(())
+    }
+
+    fn next_volume(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let path = self.volumes.advance()?;
+        self.file = BufWriter::new(File::create(path)?);
+        self.written = 0;
+        Ok(())
+    }
+}
+
+fn volume_too_small() -> io::Error {
+    io::Error::other("Volume length is too small for this archive")
+}
+
+impl Write for VolumeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let complete = self.pending.len() - self.pending.len() % BLOCK_SIZE;
+        let blocks: Vec<u8> = self.pending.drain(..complete).collect();
+        for block in blocks.chunks_exact(BLOCK_SIZE) {
+            self.write_block(block.try_into().unwrap())?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Reads an archive back from its volumes as one stream, leaving out the
+/// continuation headers
+#[derive(Debug)]
+pub struct VolumeReader {
+    volumes: Volumes,
+    file: BufReader<File>,
+    tracker: MemberTracker,
+    block: [u8; BLOCK_SIZE],
+    /// How much of `block` has been returned
+    pos: usize,
+}
+
+impl VolumeReader {
+    /// Open the first volume.
+    pub fn open(volumes: Volumes) -> Result<Self, TarError> {
+        let path = volumes.path();
+        let file = File::open(path).map_err(|e| TarError::from_io_error(e, path))?;
+        Ok(Self {
+            volumes,
+            file: BufReader::new(file),
+            tracker: MemberTracker::default(),
+            block: [0; BLOCK_SIZE],
+            pos: BLOCK_SIZE,
+        })
+    }
+
+    /// Read the next block of the archive into `self.block`, moving on to
+    /// the next volume as needed.  Returns `false` at the end of the last
+    /// volume.
+    fn next_block(&mut self) -> io::Result<bool> {
+        while !read_block(&mut self.file, &mut self.block)? {
+            if self.tracker.in_data() {
+                self.next_volume()?;
+                self.skip_continuation()?;
+            } else if self.volumes.has_next() {
+                self.next_volume()?;
+            } else {
+                return Ok(false);
+            }
+        }
+        self.tracker.advance(&self.block);
+        Ok(true)
+    }
+
+    fn next_volume(&mut self) -> io::Result<()> {
+        let path = self.volumes.advance()?;
+        self.file = BufReader::new(File::open(path)?);
+        Ok(())
+    }
+
+    /// Check the headers at the start of a volume against the member being
+    /// read, and skip them.
+    fn skip_continuation(&mut self) -> io::Result<()> {
+        let not_continued = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} is not continued on this volume",
+                    String::from_utf8_lossy(&self.tracker.name)
+                ),
+            )
+        };
+        let mut block = [0; BLOCK_SIZE];
+        if !read_block(&mut self.file, &mut block)? {
+            return Err(not_continued());
+        }
+
+        let mut header = Header::from_byte_slice(&block).clone();
+        let mut name = header.path_bytes().into_owned();
+        if header.entry_type().is_gnu_longname() {
+            let size = header.entry_size().unwrap_or(0);
+            let mut data = vec![0; size.next_multiple_of(BLOCK_SIZE as u64) as usize];
+            self.file.read_exact(&mut data)?;
+            data.truncate(data.iter().position(|&b| b == 0).unwrap_or(data.len()));
+            name = data;
+            if !read_block(&mut self.file, &mut block)? {
+                return Err(not_continued());
+            }
+            header = Header::from_byte_slice(&block).clone();
+        }
+
+        let offset = continued_at(&header);
+        if header.entry_type().as_byte() != CONTINUATION || name != self.tracker.name {
+            return Err(not_continued());
+        }
+        if offset != Some(self.tracker.offset) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "This volume is out of sequence",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Read a whole block; `false` at the end of the input.
+fn read_block(input: &mut impl Read, block: &mut [u8; BLOCK_SIZE]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < BLOCK_SIZE {
+        match input.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+impl Read for VolumeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == BLOCK_SIZE {
+            if !self.next_block()? {
+                return Ok(0);
+            }
+            self.pos = 0;
+        }
+        let n = buf.len().min(BLOCK_SIZE - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+#[path = "volume_tests.rs"]
+mod tests;
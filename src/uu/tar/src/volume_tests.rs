@@ -0,0 +1,120 @@
+// This file is part of the uutils tar package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use super::*;
+use crate::record::DEFAULT_BLOCKING_FACTOR;
+use std::fs;
+use tar::{Archive, Builder};
+
+#[test]
+fn test_parse_tape_length() {
+    assert_eq!(parse_tape_length("10").unwrap(), 10 * 1024);
+    assert_eq!(parse_tape_length("4b").unwrap(), 2048);
+    assert_eq!(parse_tape_length("1M").unwrap(), 1 << 20);
+    assert_eq!(parse_tape_length("2000b").unwrap(), 2000 * 512);
+    assert!(parse_tape_length("1b").is_err());
+    assert!(parse_tape_length("").is_err());
+    assert!(parse_tape_length("10Q").is_err());
+    assert!(parse_tape_length("-5").is_err());
+}
+
+fn volume_names(dir: &Path, count: usize) -> Vec<PathBuf> {
+    (1..=count).map(|n| dir.join(format!("v{n}.tar"))).collect()
+}
+
+#[test]
+fn test_volumes_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let names = volume_names(dir.path(), 4);
+    let long_name = format!("{}/data.bin", "d".repeat(120));
+    let data: Vec<u8> = (0..5000u32).map(|n| n as u8).collect();
+
+    let writer = VolumeWriter::create(
+        Volumes::new(names.clone(), None, "-c", DEFAULT_BLOCKING_FACTOR),
+        Some(4096),
+    )
+    .unwrap();
+    let mut builder = Builder::new(writer);
+    let mut header = Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    builder
+        .append_data(&mut header, &long_name, data.as_slice())
+        .unwrap();
+    builder.into_inner().unwrap().flush().unwrap();
+
+    // The second volume resumes the member with a long name and an M header
+    let second = fs::read(&names[1]).unwrap();
+    assert!(second.len() <= 4096);
+    let continued = Header::from_byte_slice(&second[1024..1536]);
+    assert_eq!(continued.entry_type().as_byte(), CONTINUATION);
+    let offset = parse_octal(&continued.as_gnu().unwrap().offset).unwrap();
+    assert!(offset > 0 && offset.is_multiple_of(512));
+    assert_eq!(continued.entry_size().unwrap(), data.len() as u64 - offset);
+
+    let reader =
+        VolumeReader::open(Volumes::new(names, None, "-x", DEFAULT_BLOCKING_FACTOR)).unwrap();
+    let mut archive = Archive::new(reader);
+    let mut entries = archive.entries().unwrap();
+    let mut entry = entries.next().unwrap().unwrap();
+    assert_eq!(entry.path_bytes().as_ref(), long_name.as_bytes());
+    let mut contents = Vec::new();
+    entry.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, data);
+    drop(entry);
+    assert!(entries.next().is_none());
+}
+
+#[test]
+fn test_volumes_need_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let names = volume_names(dir.path(), 1);
+    let writer = VolumeWriter::create(
+        Volumes::new(names, None, "-c", DEFAULT_BLOCKING_FACTOR),
+        Some(2048),
+    )
+    .unwrap();
+    let mut builder = Builder::new(writer);
+    let mut header = Header::new_gnu();
+    header.set_size(4096);
+    header.set_mode(0o644);
+    let err = builder
+        .append_data(&mut header, "big", [0u8; 4096].as_slice())
+        .unwrap_err();
+    assert!(err.to_string().contains("volume #2"));
+}
+
+#[test]
+fn test_volume_out_of_sequence() {
+    let dir = tempfile::tempdir().unwrap();
+    let names = volume_names(dir.path(), 4);
+    let writer = VolumeWriter::create(
+        Volumes::new(names.clone(), None, "-c", DEFAULT_BLOCKING_FACTOR),
+        Some(2048),
+    )
+    .unwrap();
+    let mut builder = Builder::new(writer);
+    let mut header = Header::new_gnu();
+    header.set_size(4608);
+    header.set_mode(0o644);
+    builder
+        .append_data(&mut header, "big", [7u8; 4608].as_slice())
+        .unwrap();
+    builder.into_inner().unwrap().flush().unwrap();
+
+    // Reading the third volume in place of the second is caught
+    let swapped = vec![
+        names[0].clone(),
+        names[2].clone(),
+        names[1].clone(),
+        names[3].clone(),
+    ];
+    let reader =
+        VolumeReader::open(Volumes::new(swapped, None, "-x", DEFAULT_BLOCKING_FACTOR)).unwrap();
+    let mut archive = Archive::new(reader);
+    let mut entry = archive.entries().unwrap().next().unwrap().unwrap();
+    let err = entry.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.to_string(), "This volume is out of sequence");
+}
@@ -3,6 +3,7 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+use std::path::PathBuf;
 use uu_tar::uu_app;
 
 #[test]
@@ -43,3 +44,194 @@ fn test_zstd_flag_parsing() {
     assert!(matches.get_flag("zstd"));
     assert!(matches.get_flag("create"));
 }
+
+#[test]
+fn test_directory_flag_parsing() {
+    let app = uu_app();
+    let result = app.try_get_matches_from(vec![
+        "tar",
+        "-cf",
+        "archive.tar",
+        "-C",
+        "build",
+        ".",
+        "--directory",
+        "docs",
+        "manual",
+    ]);
+    assert!(result.is_ok());
+    let matches = result.unwrap();
+    let dirs: Vec<_> = matches
+        .get_many::<std::path::PathBuf>("directory")
+        .unwrap()
+        .collect();
+    assert_eq!(dirs, ["build", "docs"]);
+}
+
+#[test]
+fn test_transform_flag_parsing() {
+    let app = uu_app();
+    let result = app.try_get_matches_from(vec![
+        "tar",
+        "-cf",
+        "archive.tar",
+        "--transform",
+        "s/a/b/",
+        "--xform=s/c/d/g",
+        "--show-transformed-names",
+        "file.txt",
+    ]);
+    assert!(result.is_ok());
+    let matches = result.unwrap();
+    let expressions: Vec<_> = matches.get_many::<String>("transform").unwrap().collect();
+    assert_eq!(expressions, ["s/a/b/", "s/c/d/g"]);
+    assert!(matches.get_flag("show-transformed-names"));
+}
+
+#[test]
+fn test_overwrite_options_override_each_other() {
+    let matches = uu_app()
+        .try_get_matches_from(vec![
+            "tar",
+            "-xf",
+            "archive.tar",
+            "--keep-old-files",
+            "--skip-old-files",
+            "--no-overwrite-dir",
+            "--overwrite-dir",
+        ])
+        .unwrap();
+    assert!(!matches.get_flag("keep-old-files"));
+    assert!(matches.get_flag("skip-old-files"));
+    assert!(!matches.get_flag("no-overwrite-dir"));
+    assert!(matches.get_flag("overwrite-dir"));
+}
+
+#[test]
+fn test_metadata_flag_parsing() {
+    let matches = uu_app()
+        .try_get_matches_from(vec![
+            "tar",
+            "-xmf",
+            "archive.tar",
+            "--same-permissions",
+            "--no-same-permissions",
+            "--same-owner",
+            "--numeric-owner",
+            "--delay-directory-restore",
+        ])
+        .unwrap();
+    assert!(matches.get_flag("touch"));
+    assert!(!matches.get_flag("preserve-permissions"));
+    assert!(matches.get_flag("no-same-permissions"));
+    assert!(matches.get_flag("same-owner"));
+    assert!(matches.get_flag("numeric-owner"));
+    assert!(matches.get_flag("delay-directory-restore"));
+}
+
+#[test]
+fn test_sort_flag_parsing() {
+    let matches = uu_app()
+        .try_get_matches_from(vec!["tar", "-cf", "archive.tar", "--sort=name", "dir"])
+        .unwrap();
+    assert_eq!(
+        matches.get_one::<String>("sort").map(String::as_str),
+        Some("name")
+    );
+    assert!(uu_app()
+        .try_get_matches_from(vec!["tar", "-cf", "archive.tar", "--sort=size", "dir"])
+        .is_err());
+}
+
+#[test]
+fn test_dereference_flag_parsing() {
+    let matches = uu_app()
+        .try_get_matches_from(vec![
+            "tar",
+            "-chf",
+            "archive.tar",
+            "--hard-dereference",
+            "dir",
+        ])
+        .unwrap();
+    assert!(matches.get_flag("create"));
+    assert!(matches.get_flag("dereference"));
+    assert!(matches.get_flag("hard-dereference"));
+}
+
+#[test]
+fn test_recursion_flags_override_each_other() {
+    let matches = uu_app()
+        .try_get_matches_from(vec![
+            "tar",
+            "-cf",
+            "archive.tar",
+            "--recursion",
+            "--no-recursion",
+            "--one-file-system",
+            "dir",
+        ])
+        .unwrap();
+    assert!(matches.get_flag("no-recursion"));
+    assert!(!matches.get_flag("recursion"));
+    assert!(matches.get_flag("one-file-system"));
+}
+
+#[test]
+fn test_newer_flags_override_each_other() {
+    let matches = uu_app()
+        .try_get_matches_from(vec![
+            "tar",
+            "-cf",
+            "archive.tar",
+            "--newer-mtime=@100",
+            "-N",
+            "@200",
+            "dir",
+        ])
+        .unwrap();
+    assert_eq!(matches.get_one::<u64>("newer"), Some(&200));
+    assert!(!matches.contains_id("newer-mtime"));
+
+    let matches = uu_app()
+        .try_get_matches_from(vec!["tar", "-cf", "archive.tar", "--after-date=@5", "dir"])
+        .unwrap();
+    assert_eq!(matches.get_one::<u64>("newer"), Some(&5));
+}
+
+#[test]
+fn test_multi_volume_options() {
+    let matches = uu_app()
+        .try_get_matches_from(vec![
+            "tar", "-cM", "-f", "v1.tar", "-f", "v2.tar", "-L", "2M", "-F", "next.sh", "dir",
+        ])
+        .unwrap();
+    let names: Vec<_> = matches.get_many::<PathBuf>("file").unwrap().collect();
+    assert_eq!(names, [&PathBuf::from("v1.tar"), &PathBuf::from("v2.tar")]);
+    assert_eq!(matches.get_one::<u64>("tape-length"), Some(&(2 << 20)));
+    assert_eq!(
+        matches.get_one::<String>("info-script").map(String::as_str),
+        Some("next.sh")
+    );
+    assert!(matches.get_flag("multi-volume"));
+
+    assert!(uu_app()
+        .try_get_matches_from(vec!["tar", "-cf", "v1.tar", "-L", "1M2", "dir"])
+        .is_err());
+}
+
+#[test]
+fn test_test_label_is_an_operation() {
+    let matches = uu_app()
+        .try_get_matches_from(vec!["tar", "--test-label", "-f", "a.tar", "-V", "Weekly*"])
+        .unwrap();
+    assert!(matches.get_flag("test-label"));
+    assert_eq!(
+        matches.get_one::<String>("label").map(String::as_str),
+        Some("Weekly*")
+    );
+
+    assert!(uu_app()
+        .try_get_matches_from(vec!["tar", "--test-label", "-xf", "a.tar"])
+        .is_err());
+}
@@ -26,6 +26,23 @@ fn test_tar_error_display() {
     );
 }
 
+#[test]
+fn test_label_error_display() {
+    let err = TarError::LabelMismatch {
+        label: "Weekly 42".to_string(),
+        pattern: "Daily*".to_string(),
+    };
+    assert_eq!(
+        err.to_string(),
+        "tar: Volume 'Weekly 42' does not match 'Daily*'"
+    );
+
+    let err = TarError::NotLabeled {
+        pattern: "*".to_string(),
+    };
+    assert_eq!(err.to_string(), "tar: Archive not labeled to match '*'");
+}
+
 #[test]
 fn test_tar_error_code() {
     use uucore::error::UError;
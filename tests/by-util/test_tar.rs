@@ -524,175 +524,2210 @@ fn test_extract_directory_structure() {
     assert_eq!(at.read("testdir/subdir/file2.txt"), "content2");
 }
 
+#[test]
+fn test_extract_with_directory_option() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt"]).succeeds();
+    at.mkdir("target");
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "-C", "target"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_output();
+
+    assert_eq!(at.read("target/file.txt"), "content");
+}
+
+#[test]
+fn test_extract_with_missing_directory_fails() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt"]).succeeds();
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--directory", "missing"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("missing: Cannot chdir");
+}
+
+#[test]
+fn test_create_with_directory_switches_between_operands() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("build");
+    at.write("build/app.bin", "app");
+    at.mkdir("docs");
+    at.mkdir("docs/manual");
+    at.write("docs/manual/index.txt", "manual");
+
+    ucmd.args(&[
+        "-cf",
+        "archive.tar",
+        "-C",
+        "build",
+        ".",
+        "-C",
+        "../docs",
+        "manual",
+    ])
+    .succeeds()
+    .no_output();
+
+    new_ucmd!()
+        .args(&["-tf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_contains("app.bin")
+        .stdout_contains("manual/index.txt")
+        .stdout_does_not_contain("build")
+        .stdout_does_not_contain("docs");
+
+    at.mkdir("out");
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "-C", "out"])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    assert_eq!(at.read("out/app.bin"), "app");
+    assert_eq!(at.read("out/manual/index.txt"), "manual");
+}
+
+#[test]
+fn test_create_directory_applies_only_to_following_operands() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("top.txt", "top");
+    at.mkdir("sub");
+    at.write("sub/inner.txt", "inner");
+
+    ucmd.args(&["-cf", "archive.tar", "top.txt", "-C", "sub", "inner.txt"])
+        .succeeds();
+
+    new_ucmd!()
+        .args(&["-tf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("top.txt\ninner.txt\n");
+
+    // Without -C, inner.txt would have to be found in the current directory
+    new_ucmd!()
+        .args(&["-cf", "archive2.tar", "top.txt", "inner.txt"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("inner.txt");
+}
+
+#[test]
+fn test_extract_strip_components() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("project-1.2.3");
+    at.mkdir("project-1.2.3/src");
+    at.write("project-1.2.3/src/lib.rs", "lib");
+    at.write("project-1.2.3/README", "readme");
+    ucmd.args(&["-cf", "archive.tar", "project-1.2.3"])
+        .succeeds();
+    at.mkdir("vendor");
+
+    let result = new_ucmd!()
+        .args(&[
+            "-xvf",
+            "archive.tar",
+            "-C",
+            "vendor",
+            "--strip-components=1",
+        ])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    result
+        .stdout_contains("src/lib.rs")
+        .stdout_contains("README")
+        .stdout_does_not_contain("project-1.2.3");
+    assert_eq!(at.read("vendor/src/lib.rs"), "lib");
+    assert_eq!(at.read("vendor/README"), "readme");
+    assert!(!at.dir_exists("vendor/project-1.2.3"));
+}
+
+#[test]
+fn test_list_strip_components() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("project");
+    at.write("project/file.txt", "content");
+    ucmd.args(&["-cf", "archive.tar", "project"]).succeeds();
+
+    new_ucmd!()
+        .args(&["-tf", "archive.tar", "--strip-components", "1"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("file.txt\n");
+}
+
+#[test]
+fn test_strip_components_invalid_value() {
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--strip-components=many"])
+        .fails()
+        .code_is(2)
+        .stderr_contains("--strip-components");
+}
+
+#[test]
+fn test_create_with_transform() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir_all("build/bin");
+    at.write("build/bin/tool", "tool");
+    ucmd.args(&[
+        "-cf",
+        "archive.tar",
+        "--transform",
+        "s,^build/,pkg/usr/,",
+        "build/bin/tool",
+    ])
+    .succeeds();
+
+    new_ucmd!()
+        .args(&["-tf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("pkg/usr/bin/tool\n");
+}
+
+#[test]
+fn test_extract_with_transform() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("docs");
+    at.write("docs/README", "readme");
+    ucmd.args(&["-cf", "archive.tar", "docs"]).succeeds();
+    at.remove("docs/README");
+    at.rmdir("docs");
+
+    new_ucmd!()
+        .args(&[
+            "-xf",
+            "archive.tar",
+            "--xform",
+            "s/docs/manual/;s/README/index/",
+        ])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    assert!(!at.dir_exists("docs"));
+    assert_eq!(at.read("manual/index"), "readme");
+}
+
+#[test]
+fn test_show_transformed_names() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&[
+        "-cvf",
+        "archive.tar",
+        "--transform",
+        "s/file/renamed/",
+        "--show-transformed-names",
+        "file.txt",
+    ])
+    .succeeds()
+    .stdout_is("renamed.txt\n");
+
+    new_ucmd!()
+        .args(&["-tf", "archive.tar", "--transform", "s/renamed/other/"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("renamed.txt\n");
+
+    new_ucmd!()
+        .args(&[
+            "-tf",
+            "archive.tar",
+            "--transform",
+            "s/renamed/other/",
+            "--show-transformed-names",
+        ])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("other.txt\n");
+}
+
+#[test]
+fn test_transform_invalid_expression() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&[
+        "-cf",
+        "archive.tar",
+        "--transform",
+        "s/unterminated",
+        "file.txt",
+    ])
+    .fails()
+    .code_is(2)
+    .stderr_contains("tar: ");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_with_metadata_overrides() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("release");
+    at.set_mode("release", 0o775);
+    at.write("release/run.sh", "#!/bin/sh\n");
+    at.set_mode("release/run.sh", 0o764);
+    at.write("release/README", "readme");
+    at.set_mode("release/README", 0o660);
+
+    ucmd.args(&[
+        "-cf",
+        "archive.tar",
+        "--owner=root:0",
+        "--group=wheel:0",
+        "--mode=go-w,a+rX",
+        "--mtime=@1700000000",
+        "release",
+    ])
+    .succeeds();
+
+    let archive_bytes = at.read_bytes("archive.tar");
+    let mut archive = TarRsArchive::new(Cursor::new(archive_bytes));
+    let mut modes = Vec::new();
+    for entry in archive.entries().unwrap() {
+        let entry = entry.unwrap();
+        let header = entry.header();
+        assert_eq!(header.uid().unwrap(), 0);
+        assert_eq!(header.gid().unwrap(), 0);
+        assert_eq!(header.username().unwrap(), Some("root"));
+        assert_eq!(header.groupname().unwrap(), Some("wheel"));
+        assert_eq!(header.mtime().unwrap(), 1_700_000_000);
+        let path = entry.path().unwrap().to_string_lossy().into_owned();
+        modes.push((path, header.mode().unwrap() & 0o7777));
+    }
+    modes.sort();
+    assert_eq!(
+        modes,
+        [
+            ("release/".to_string(), 0o755),
+            ("release/README".to_string(), 0o644),
+            ("release/run.sh".to_string(), 0o755),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_numeric_owner() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.write("file.txt", "content");
+
+    let owner_names = |name: &str| {
+        let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes(name)));
+        let entry = archive.entries().unwrap().next().unwrap().unwrap();
+        let header = entry.header();
+        (
+            header.username().unwrap().unwrap_or_default().to_string(),
+            header.groupname().unwrap().unwrap_or_default().to_string(),
+        )
+    };
+
+    // The owner of the file on disk is recorded by name as well as id
+    ucmd.args(&["-cf", "named.tar", "file.txt"]).succeeds();
+    let (user, group) = owner_names("named.tar");
+    assert!(!user.is_empty() && !group.is_empty());
+
+    new_ucmd!()
+        .args(&["-cf", "numeric.tar", "--numeric-owner", "file.txt"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(owner_names("numeric.tar"), (String::new(), String::new()));
+}
+
+#[test]
+fn test_create_clamp_mtime() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&[
+        "-cf",
+        "archive.tar",
+        "--mtime=2000-01-01T00:00:00Z",
+        "--clamp-mtime",
+        "file.txt",
+    ])
+    .succeeds();
+    new_ucmd!()
+        .args(&[
+            "-cf",
+            "future.tar",
+            "--mtime=@4102444800",
+            "--clamp-mtime",
+            "file.txt",
+        ])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    let mtime_of = |name: &str| {
+        let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes(name)));
+        let entry = archive.entries().unwrap().next().unwrap().unwrap();
+        entry.header().mtime().unwrap()
+    };
+    // Newer files are clamped, older ones keep their own time
+    assert_eq!(mtime_of("archive.tar"), 946_684_800);
+    assert!(mtime_of("future.tar") < 4_102_444_800);
+}
+
+#[test]
+fn test_create_invalid_metadata_overrides() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&["-cf", "archive.tar", "--owner=nobody-at-all", "file.txt"])
+        .fails()
+        .code_is(2)
+        .stderr_contains("Invalid owner");
+    new_ucmd!()
+        .args(&["-cf", "archive.tar", "--mode=a+q", "file.txt"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2);
+    new_ucmd!()
+        .args(&["-cf", "archive.tar", "--mtime=someday", "file.txt"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("Invalid date format");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_with_owner_and_group_maps() {
+    use std::os::unix::fs::MetadataExt;
+
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    let meta = std::fs::metadata(at.plus("file.txt")).unwrap();
+    at.write("owners", &format!("+{} builder:1001\n", meta.uid()));
+    at.write(
+        "groups",
+        &format!("# build group\n+{} staff:50\n", meta.gid()),
+    );
+
+    ucmd.args(&[
+        "-cf",
+        "archive.tar",
+        "--owner-map=owners",
+        "--group-map",
+        "groups",
+        "file.txt",
+    ])
+    .succeeds();
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    let entry = archive.entries().unwrap().next().unwrap().unwrap();
+    let header = entry.header();
+    assert_eq!(header.uid().unwrap(), 1001);
+    assert_eq!(header.username().unwrap(), Some("builder"));
+    assert_eq!(header.gid().unwrap(), 50);
+    assert_eq!(header.groupname().unwrap(), Some("staff"));
+}
+
+#[test]
+fn test_create_invalid_owner_map() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    at.write("owners", "+0 root:0\njust-one-field\n");
+    ucmd.args(&["-cf", "archive.tar", "--owner-map=owners", "file.txt"])
+        .fails()
+        .code_is(2)
+        .stderr_contains("tar: owners:2: Invalid map entry 'just-one-field'");
+    new_ucmd!()
+        .args(&["-cf", "archive.tar", "--group-map=missing", "file.txt"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("missing: Cannot open");
+}
+
+#[test]
+fn test_create_reproducible_archive() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    // The same tree, populated in opposite orders
+    let files = [
+        "tree/b.txt",
+        "tree/sub/z.txt",
+        "tree/a.txt",
+        "tree/sub/y.txt",
+    ];
+    for (root, order) in [
+        ("one", files.to_vec()),
+        ("two", files.iter().rev().copied().collect()),
+    ] {
+        at.mkdir_all(&format!("{root}/tree/sub"));
+        for file in order {
+            at.write(&format!("{root}/{file}"), file);
+        }
+    }
+
+    let reproducible = [
+        "--sort=name",
+        "--mtime=@1700000000",
+        "--clamp-mtime",
+        "--owner=0",
+        "--group=0",
+        "--numeric-owner",
+        "--pax-option=delete=atime,delete=ctime",
+    ];
+    ucmd.args(&["-cf", "one.tar", "-C", "one"])
+        .args(&reproducible)
+        .arg("tree")
+        .succeeds();
+    new_ucmd!()
+        .args(&["-cf", "two.tar", "-C", "two"])
+        .args(&reproducible)
+        .arg("tree")
+        .current_dir(at.as_string())
+        .succeeds();
+
+    assert_eq!(at.read_bytes("one.tar"), at.read_bytes("two.tar"));
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("one.tar")));
+    let names: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "tree/",
+            "tree/a.txt",
+            "tree/b.txt",
+            "tree/sub/",
+            "tree/sub/y.txt",
+            "tree/sub/z.txt"
+        ]
+    );
+}
+
+#[test]
+fn test_create_source_date_epoch() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt"])
+        .env("SOURCE_DATE_EPOCH", "946684800")
+        .succeeds();
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    let entry = archive.entries().unwrap().next().unwrap().unwrap();
+    assert_eq!(entry.header().mtime().unwrap(), 946_684_800);
+
+    new_ucmd!()
+        .args(&["-cf", "archive.tar", "file.txt"])
+        .env("SOURCE_DATE_EPOCH", "yesterday")
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("Invalid SOURCE_DATE_EPOCH");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_stores_symlinks() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("dir");
+    at.write("dir/file.txt", "content");
+    at.symlink_file("file.txt", "dir/link");
+    at.symlink_file("missing", "dir/dangling");
+    at.mkdir("target");
+    at.write("target/inner.txt", "inner");
+    at.symlink_dir("../target", "dir/linkdir");
+
+    ucmd.args(&["-cf", "archive.tar", "--sort=name", "dir"])
+        .succeeds();
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    let members: Vec<_> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (
+                entry.path().unwrap().to_string_lossy().into_owned(),
+                entry.header().entry_type().is_symlink(),
+                entry
+                    .link_name()
+                    .unwrap()
+                    .map(|target| target.to_string_lossy().into_owned()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        members,
+        [
+            ("dir/".to_string(), false, None),
+            (
+                "dir/dangling".to_string(),
+                true,
+                Some("missing".to_string())
+            ),
+            ("dir/file.txt".to_string(), false, None),
+            ("dir/link".to_string(), true, Some("file.txt".to_string())),
+            (
+                "dir/linkdir".to_string(),
+                true,
+                Some("../target".to_string())
+            ),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_dereference() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("target");
+    at.write("target/inner.txt", "inner");
+    at.write("file.txt", "content");
+    at.symlink_file("file.txt", "link");
+    at.symlink_dir("target", "linkdir");
+
+    ucmd.args(&["-chf", "archive.tar", "link", "linkdir"])
+        .succeeds();
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    let mut members = Vec::new();
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        assert!(!entry.header().entry_type().is_symlink());
+        let mut content = String::new();
+        entry.read_to_string(&mut content).unwrap();
+        members.push((
+            entry.path().unwrap().to_string_lossy().into_owned(),
+            content,
+        ));
+    }
+    assert_eq!(
+        members,
+        [
+            ("link".to_string(), "content".to_string()),
+            ("linkdir/".to_string(), String::new()),
+            ("linkdir/inner.txt".to_string(), "inner".to_string()),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_hard_links() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("dir");
+    at.write("dir/a.txt", "shared content");
+    at.hard_link("dir/a.txt", "dir/b.txt");
+    at.hard_link("dir/a.txt", "dir/c.txt");
+
+    ucmd.args(&["-cf", "archive.tar", "--sort=name", "dir"])
+        .succeeds();
+    new_ucmd!()
+        .args(&["-cf", "copies.tar", "--hard-dereference", "dir"])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    let members: Vec<_> = archive
+        .entries()
+        .unwrap()
+        .skip(1)
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (
+                entry.path().unwrap().to_string_lossy().into_owned(),
+                entry.header().entry_size().unwrap(),
+                entry
+                    .link_name()
+                    .unwrap()
+                    .map(|target| target.to_string_lossy().into_owned()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        members,
+        [
+            ("dir/a.txt".to_string(), 14, None),
+            ("dir/b.txt".to_string(), 0, Some("dir/a.txt".to_string())),
+            ("dir/c.txt".to_string(), 0, Some("dir/a.txt".to_string())),
+        ]
+    );
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("copies.tar")));
+    for entry in archive.entries().unwrap().skip(1) {
+        let entry = entry.unwrap();
+        assert!(!entry.header().entry_type().is_hard_link());
+        assert_eq!(entry.header().entry_size().unwrap(), 14);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_hard_links_to_dot_prefixed_operands() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("a1", "shared content");
+    at.hard_link("a1", "a2");
+
+    ucmd.args(&["-cf", "archive.tar", "./a1", "./a2"])
+        .succeeds();
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    let members: Vec<_> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (
+                entry.path().unwrap().to_string_lossy().into_owned(),
+                entry
+                    .link_name()
+                    .unwrap()
+                    .map(|target| target.to_string_lossy().into_owned()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        members,
+        [
+            ("a1".to_string(), None),
+            ("a2".to_string(), Some("a1".to_string())),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_check_links() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("dir");
+    at.write("dir/a.txt", "shared content");
+    at.hard_link("dir/a.txt", "b.txt");
+    at.write("dir/alone.txt", "alone");
+
+    ucmd.args(&["-clf", "archive.tar", "dir"])
+        .succeeds()
+        .stderr_is("tar: Missing links to 'dir/a.txt'.\n");
+    new_ucmd!()
+        .args(&["-clf", "archive.tar", "dir", "b.txt"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_stderr();
+}
+
+#[test]
+fn test_create_no_recursion() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir_all("dir/sub");
+    at.write("dir/file.txt", "content");
+    at.write("dir/sub/inner.txt", "inner");
+
+    ucmd.args(&[
+        "-cf",
+        "archive.tar",
+        "--no-recursion",
+        "dir",
+        "dir/sub/inner.txt",
+    ])
+    .succeeds();
+    new_ucmd!()
+        .args(&[
+            "-cf",
+            "again.tar",
+            "--no-recursion",
+            "--recursion",
+            "--sort=name",
+            "dir",
+        ])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    let names_of = |name: &str| -> Vec<String> {
+        let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes(name)));
+        archive
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+            .collect()
+    };
+    assert_eq!(names_of("archive.tar"), ["dir/", "dir/sub/inner.txt"]);
+    assert_eq!(
+        names_of("again.tar"),
+        ["dir/", "dir/file.txt", "dir/sub/", "dir/sub/inner.txt"]
+    );
+}
+
+#[test]
+fn test_create_one_file_system_same_device() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir_all("dir/sub");
+    at.write("dir/sub/inner.txt", "inner");
+
+    ucmd.args(&["-cf", "archive.tar", "--one-file-system", "dir"])
+        .succeeds()
+        .no_stderr();
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    assert_eq!(archive.entries().unwrap().count(), 3);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_root_operand() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    // The root itself, as in a full backup with `--one-file-system /`
+    ucmd.args(&["-cf", "archive.tar", "--one-file-system", "--no-recursion"])
+        .args(&["/", "//", "/."])
+        .succeeds()
+        .stderr_contains("tar: Removing leading `/' from member names");
+
+    new_ucmd!()
+        .args(&["-tf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("./\n./\n./\n");
+}
+
+#[test]
+fn test_create_newer_mtime() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("dir");
+    at.write("dir/old.txt", "old");
+    at.write("dir/new.txt", "new");
+    std::fs::File::options()
+        .write(true)
+        .open(at.plus("dir/old.txt"))
+        .unwrap()
+        .set_modified(UNIX_EPOCH + Duration::from_secs(1_262_304_000))
+        .unwrap();
+
+    ucmd.args(&[
+        "-cvf",
+        "archive.tar",
+        "--newer-mtime",
+        "2020-01-01 00:00",
+        "--sort=name",
+        "dir",
+    ])
+    .succeeds()
+    .stdout_is("dir/\ndir/new.txt\n")
+    .stderr_is("tar: dir/old.txt: file is unchanged; not dumped\n");
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    let names: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["dir/", "dir/new.txt"]);
+}
+
+#[test]
+fn test_create_newer_than_reference_file() {
+    use std::time::{Duration, SystemTime};
+
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("dir");
+    at.write("dir/file.txt", "content");
+    at.write("reference", "");
+    std::fs::File::options()
+        .write(true)
+        .open(at.plus("reference"))
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(86_400))
+        .unwrap();
+
+    // Neither the data nor the inode changed after the reference
+    ucmd.args(&["-cf", "archive.tar", "-N", "./reference", "dir"])
+        .succeeds()
+        .no_stderr();
+
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("archive.tar")));
+    let names: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["dir/"]);
+
+    new_ucmd!()
+        .args(&["-cf", "archive.tar", "--after-date", "someday", "dir"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2);
+}
+
+#[test]
+fn test_create_remove_files() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir_all("logs/old");
+    at.write("logs/a.log", "a");
+    at.write("logs/old/b.log", "b");
+    at.write("keep.txt", "keep");
+
+    ucmd.args(&["-czf", "logs.tar.gz", "--remove-files", "logs"])
+        .succeeds()
+        .no_stderr();
+
+    assert!(!at.dir_exists("logs"));
+    assert!(at.file_exists("keep.txt"));
+
+    // Nothing is removed when the archive can't be completed
+    new_ucmd!()
+        .args(&[
+            "-cf",
+            "partial.tar",
+            "--remove-files",
+            "keep.txt",
+            "missing",
+        ])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2);
+    assert!(at.file_exists("keep.txt"));
+
+    at.mkdir("out");
+    new_ucmd!()
+        .args(&["-xzf", "logs.tar.gz", "-C", "out"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.read("out/logs/a.log"), "a");
+    assert_eq!(at.read("out/logs/old/b.log"), "b");
+}
+
+#[test]
+fn test_create_remove_files_keeps_unarchived() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("logs");
+    at.write("logs/a.log", "a");
+    at.write("logs/skip.log", "skip");
+
+    // Members transformed away were never archived, so stay on disk
+    ucmd.args(&[
+        "-cf",
+        "logs.tar",
+        "--remove-files",
+        "--transform=s,.*skip.log,,",
+        "logs",
+    ])
+    .fails()
+    .code_is(2)
+    .stderr_contains("tar: logs: Cannot rmdir:");
+
+    assert!(!at.file_exists("logs/a.log"));
+    assert!(at.file_exists("logs/skip.log"));
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("logs.tar")));
+    assert_eq!(archive.entries().unwrap().count(), 2);
+}
+
+#[test]
+fn test_create_pads_to_record_size() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "hello");
+    ucmd.args(&["-cf", "default.tar", "file.txt"]).succeeds();
+
+    // Header, data and two end-of-archive blocks, zero padded to a record
+    let bytes = at.read_bytes("default.tar");
+    assert_eq!(bytes.len(), 20 * TAR_BLOCK_SIZE);
+    assert!(bytes[2 * TAR_BLOCK_SIZE..].iter().all(|&b| b == 0));
+
+    new_ucmd!()
+        .args(&["-cf", "small.tar", "-b", "3", "file.txt"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.read_bytes("small.tar").len(), 6 * TAR_BLOCK_SIZE);
+
+    new_ucmd!()
+        .args(&["-cf", "bytes.tar", "--record-size=1536", "file.txt"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.read_bytes("bytes.tar"), at.read_bytes("small.tar"));
+
+    new_ucmd!()
+        .args(&["-cf", "bad.tar", "--record-size=1000", "file.txt"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("1000: Record size must be a multiple of 512.");
+}
+
+#[test]
+fn test_extract_read_full_records_from_stdin() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "hello");
+    ucmd.args(&["-cf", "archive.tar", "-b", "4", "file.txt"])
+        .succeeds();
+    let archive = at.read_bytes("archive.tar");
+    at.remove("file.txt");
+
+    new_ucmd!()
+        .args(&["-xB", "-b", "4", "-f", "-"])
+        .current_dir(at.as_string())
+        .pipe_in(archive)
+        .succeeds()
+        .no_output();
+    assert_eq!(at.read("file.txt"), "hello");
+}
+
+#[test]
+fn test_list_concatenated_archives_ignore_zeros() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("a.txt", "a");
+    at.write("b.txt", "b");
+    ucmd.args(&["-cf", "a.tar", "a.txt"]).succeeds();
+    new_ucmd!()
+        .args(&["-cf", "b.tar", "b.txt"])
+        .current_dir(at.as_string())
+        .succeeds();
+    let mut joined = at.read_bytes("a.tar");
+    joined.extend(at.read_bytes("b.tar"));
+    at.write_bytes("joined.tar", &joined);
+
+    // The end of the first archive hides the second without -i
+    new_ucmd!()
+        .args(&["-tf", "joined.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("a.txt\n");
+    new_ucmd!()
+        .args(&["-tif", "joined.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("a.txt\nb.txt\n")
+        .no_stderr();
+
+    // The end of an archive is not worth a warning, even verbosely
+    new_ucmd!()
+        .args(&["-tvif", "joined.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_stderr();
+
+    at.mkdir("out");
+    new_ucmd!()
+        .args(&["-xif", "joined.tar", "-C", "out"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.read("out/a.txt"), "a");
+    assert_eq!(at.read("out/b.txt"), "b");
+}
+
+#[test]
+fn test_extract_lone_zero_block() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("a.txt", "a");
+    at.write("b.txt", "b");
+    ucmd.args(&["-cf", "archive.tar", "-b", "1", "a.txt", "b.txt"])
+        .succeeds();
+
+    // Slip a zero block in between the two members
+    let bytes = at.read_bytes("archive.tar");
+    let mut damaged = bytes[..2 * TAR_BLOCK_SIZE].to_vec();
+    damaged.extend_from_slice(&[0; TAR_BLOCK_SIZE]);
+    damaged.extend_from_slice(&bytes[2 * TAR_BLOCK_SIZE..]);
+    at.write_bytes("damaged.tar", &damaged);
+
+    at.mkdir("out");
+    new_ucmd!()
+        .args(&["-xvif", "damaged.tar", "-C", "out"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stderr_is("tar: A lone zero block at 3\n");
+    assert_eq!(at.read("out/b.txt"), "b");
+
+    // The same goes for an archive cut short after a single zero block
+    at.write_bytes("truncated.tar", &damaged[..3 * TAR_BLOCK_SIZE]);
+    new_ucmd!()
+        .args(&["-tvif", "truncated.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stderr_is("tar: A lone zero block at 3\n");
+}
+
+#[test]
+fn test_multi_volume_rejects_compression() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&["-czM", "-L", "10", "-f", "v1.tar", "file.txt"])
+        .fails()
+        .code_is(2)
+        .stderr_contains("Cannot use multi-volume compressed archives");
+
+    new_ucmd!()
+        .args(&["-cf", "v1.tar", "-L", "1b", "file.txt"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("Invalid tape length '1b'");
+}
+
+#[test]
+fn test_create_volume_label() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&["-cf", "archive.tar", "-V", "Backup 2026-10", "file.txt"])
+        .succeeds()
+        .no_output();
+
+    let bytes = at.read_bytes("archive.tar");
+    let label = TarRsHeader::from_byte_slice(&bytes[..TAR_BLOCK_SIZE]);
+    assert_eq!(label.entry_type().as_byte(), b'V');
+    assert_eq!(label.path_bytes().as_ref(), b"Backup 2026-10");
+
+    new_ucmd!()
+        .args(&["-tf", "archive.tar", "-V", "Backup *"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("Backup 2026-10\nfile.txt\n");
+
+    // The label is checked, but never extracted
+    at.mkdir("out");
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "-V", "Daily*", "-C", "out"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("tar: Volume 'Backup 2026-10' does not match 'Daily*'");
+    assert!(!at.file_exists("out/file.txt"));
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "-C", "out"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_output();
+    assert_eq!(at.read("out/file.txt"), "content");
+    assert!(!at.file_exists("out/Backup 2026-10"));
+
+    new_ucmd!()
+        .args(&["-cf", "plain.tar", "file.txt"])
+        .current_dir(at.as_string())
+        .succeeds();
+    new_ucmd!()
+        .args(&["-tf", "plain.tar", "-V", "*"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("tar: Archive not labeled to match '*'");
+}
+
+#[test]
+fn test_test_label() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&["-cf", "archive.tar", "--label=Weekly 42", "file.txt"])
+        .succeeds();
+
+    new_ucmd!()
+        .args(&["--test-label", "-f", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("Weekly 42\n");
+    new_ucmd!()
+        .args(&["--test-label", "-f", "archive.tar", "Daily*", "Weekly*"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_output();
+    // With patterns, only the exit status tells whether the label matched
+    new_ucmd!()
+        .args(&["--test-label", "-f", "archive.tar", "-V", "Daily*"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(1)
+        .no_output();
+}
+
+#[test]
+fn test_volume_label_from_gnu() {
+    // tests/fixtures/tar: `f` archived by GNU tar 1.34 with `-V 'Daily 1'`,
+    // whose label header leaves its size, mode and ids empty
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    ucmd.args(&["-tf", "gnu-labelled.tar", "-V", "Daily*"])
+        .succeeds()
+        .stdout_is("Daily 1\nf\n");
+    new_ucmd!()
+        .args(&["--test-label", "-f", "gnu-labelled.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("Daily 1\n");
+
+    at.mkdir("out");
+    new_ucmd!()
+        .args(&["-xf", "gnu-labelled.tar", "-V", "Daily 1", "-C", "out"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_output();
+    assert_eq!(at.read("out/f"), "hello\n");
+}
+
+#[test]
+fn test_totals() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.write("file.txt", "content");
+
+    ucmd.args(&["--totals", "-cf", "archive.tar", "file.txt"])
+        .succeeds()
+        .stderr_contains("Total bytes written: 10240 (10KiB, ");
+    new_ucmd!()
+        .args(&["--totals", "-tf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("file.txt\n")
+        .stderr_contains("Total bytes read: 10240 (10KiB, ");
+    new_ucmd!()
+        .args(&["--totals=KILL", "-tf", "archive.tar"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2);
+}
+
+#[test]
+fn test_checkpoint() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.write("file.txt", &"x".repeat(1000));
+
+    // A header, two data blocks and the two zero blocks, one per record
+    ucmd.args(&[
+        "-cf",
+        "archive.tar",
+        "-b",
+        "1",
+        "--checkpoint=2",
+        "file.txt",
+    ])
+    .succeeds()
+    .stderr_is("tar: Write checkpoint 2\ntar: Write checkpoint 4\n");
+    new_ucmd!()
+        .args(&["-tf", "archive.tar", "-b", "1", "--checkpoint=2"])
+        .args(&["--checkpoint-action=echo=%s %u", "--checkpoint-action=dot"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("file.txt\n")
+        .stderr_is("tar: read 2\n.tar: read 4\n.");
+    new_ucmd!()
+        .args(&["-tf", "archive.tar", "--checkpoint-action=explode"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2);
+}
+
+#[test]
+fn test_checkpoint_counts_compressed_records() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.write("file.txt", &"x".repeat(20_000));
+
+    // 41 blocks of archive squeeze into less than one record
+    let result = ucmd
+        .args(&["-czf", "archive.tar.gz", "-b", "1", "--checkpoint=1"])
+        .args(&["--totals", "file.txt"])
+        .succeeds();
+    assert!(!result.stderr_str().contains("checkpoint"));
+    result.stderr_contains(format!(
+        "Total bytes written: {} (",
+        at.read_bytes("archive.tar.gz").len()
+    ));
+    new_ucmd!()
+        .args(&["-tzf", "archive.tar.gz", "-b", "1", "--checkpoint=1"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("file.txt\n")
+        .no_stderr();
+}
+
 // Round-trip tests
 
 #[test]
-fn test_roundtrip_empty_files() {
+fn test_roundtrip_empty_files() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    // Create empty files
+    at.touch("empty1.txt");
+    at.touch("empty2.txt");
+
+    // Create archive
+    ucmd.args(&["-cf", "archive.tar", "empty1.txt", "empty2.txt"])
+        .succeeds();
+
+    // Remove originals
+    at.remove("empty1.txt");
+    at.remove("empty2.txt");
+
+    // Extract
+    new_ucmd!()
+        .arg("-xf")
+        .arg(at.plus("archive.tar"))
+        .current_dir(at.as_string())
+        .succeeds();
+
+    // Verify empty files exist and are still empty
+    assert!(at.file_exists("empty1.txt"));
+    assert!(at.file_exists("empty2.txt"));
+    assert_eq!(at.read("empty1.txt"), "");
+    assert_eq!(at.read("empty2.txt"), "");
+}
+
+// Error handling and exit code tests
+
+#[test]
+#[cfg(unix)]
+fn test_create_permission_denied() {
+    if rustix::process::geteuid().is_root() {
+        eprintln!("skipping: running as root");
+        return;
+    }
+
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    at.mkdir("readonly");
+
+    // Make directory read-only
+    at.set_readonly("readonly");
+
+    ucmd.args(&["-cf", "readonly/archive.tar", "file.txt"])
+        .fails()
+        .code_is(2)
+        .stderr_contains("readonly/archive.tar");
+
+    // Cleanup - restore permissions so test cleanup can work
+    at.set_mode("readonly", 0o755);
+}
+
+#[test]
+fn test_extract_corrupted_archive() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    // Create a corrupted tar file (invalid header)
+    at.write("corrupted.tar", "This is not a valid tar file content");
+
+    ucmd.args(&["-xf", "corrupted.tar"]).fails().code_is(2);
+}
+
+/// Damaged copies of an archive holding `a.txt` (blocks 0-1), `b.txt`
+/// (blocks 2-4) and `c.txt` (blocks 5-6), with the members each should
+/// still yield.
+fn damaged_archive_corpus(archive: &[u8]) -> Vec<(&'static str, Vec<u8>, Vec<&'static str>)> {
+    let block = |n: usize| n * TAR_BLOCK_SIZE;
+    let mut corpus = Vec::new();
+
+    let mut bad_checksum = archive.to_vec();
+    bad_checksum[block(2)] ^= 0x20;
+    corpus.push(("bad checksum", bad_checksum, vec!["a.txt", "c.txt"]));
+
+    let mut garbage_block = archive[..block(2)].to_vec();
+    garbage_block.extend_from_slice(&[0xaa; TAR_BLOCK_SIZE]);
+    garbage_block.extend_from_slice(&archive[block(2)..]);
+    corpus.push((
+        "garbage between members",
+        garbage_block,
+        vec!["a.txt", "b.txt", "c.txt"],
+    ));
+
+    let mut bad_first = archive.to_vec();
+    bad_first[..100].fill(b'?');
+    corpus.push(("damaged first header", bad_first, vec!["b.txt", "c.txt"]));
+
+    let mut bad_last = archive.to_vec();
+    bad_last[block(5) + 148..block(5) + 156].fill(b'9');
+    corpus.push(("damage before the end", bad_last, vec!["a.txt", "b.txt"]));
+
+    let mut overwritten = archive.to_vec();
+    overwritten[block(2)..block(4)].fill(0x55);
+    corpus.push((
+        "overwritten header and data",
+        overwritten,
+        vec!["a.txt", "c.txt"],
+    ));
+
+    corpus
+}
+
+#[test]
+fn test_extract_recovers_from_damaged_headers() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("a.txt", "alpha");
+    at.write("b.txt", &"b".repeat(1000));
+    at.write("c.txt", "gamma");
+    ucmd.args(&["-cf", "archive.tar", "-b", "1", "a.txt", "b.txt", "c.txt"])
+        .succeeds();
+    let archive = at.read_bytes("archive.tar");
+
+    for (index, (damage, bytes, survivors)) in
+        damaged_archive_corpus(&archive).into_iter().enumerate()
+    {
+        let name = format!("damaged{index}.tar");
+        let out = format!("out{index}");
+        at.write_bytes(&name, &bytes);
+        at.mkdir(&out);
+
+        let result = new_ucmd!()
+            .args(&["-xf", &name, "-C", &out])
+            .current_dir(at.as_string())
+            .fails();
+        result
+            .code_is(2)
+            .stderr_contains("tar: Skipping to next header")
+            .stderr_contains("tar: Exiting with failure status due to previous errors");
+        for member in ["a.txt", "b.txt", "c.txt"] {
+            assert_eq!(
+                at.file_exists(format!("{out}/{member}")),
+                survivors.contains(&member),
+                "{damage}: {member}"
+            );
+        }
+        for member in survivors {
+            assert_eq!(
+                at.read(&format!("{out}/{member}")),
+                at.read(member),
+                "{damage}: {member}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_list_damaged_archive() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("a.txt", "alpha");
+    at.write("b.txt", &"b".repeat(1000));
+    at.write("c.txt", "gamma");
+    ucmd.args(&["-cf", "archive.tar", "-b", "1", "a.txt", "b.txt", "c.txt"])
+        .succeeds();
+    let mut bytes = at.read_bytes("archive.tar");
+    bytes[..100].fill(b'?');
+    at.write_bytes("damaged.tar", &bytes);
+
+    new_ucmd!()
+        .args(&["-tf", "damaged.tar"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stdout_is("b.txt\nc.txt\n")
+        .stderr_contains(
+            "tar: This does not look like a tar archive\ntar: Skipping to next header\n",
+        );
+}
+
+#[test]
+fn test_create_with_dash_in_filename() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    // Create files starting with dash
+    at.write("-dash-file.txt", "content with dash");
+    at.write("normal.txt", "normal content");
+
+    ucmd.args(&["-cf", "archive.tar", "--", "-dash-file.txt", "normal.txt"])
+        .succeeds();
+
+    assert!(at.file_exists("archive.tar"));
+
+    // Verify extraction works
+    at.remove("-dash-file.txt");
+    at.remove("normal.txt");
+
+    new_ucmd!()
+        .arg("-xf")
+        .arg(at.plus("archive.tar"))
+        .current_dir(at.as_string())
+        .succeeds();
+
+    assert!(at.file_exists("-dash-file.txt"));
+    assert_eq!(at.read("-dash-file.txt"), "content with dash");
+}
+
+// CLI argument handling tests
+
+#[test]
+fn test_mixed_short_and_long_options() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+
+    // Test mixing -x with --file
+    ucmd.args(&["-c", "--file=archive.tar", "file.txt"])
+        .succeeds();
+
+    assert!(at.file_exists("archive.tar"));
+
+    at.remove("file.txt");
+
+    // Test extraction with mixed options
+    new_ucmd!()
+        .args(&["-x", "--file", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    assert!(at.file_exists("file.txt"));
+}
+
+#[test]
+fn test_option_order_variations() {
     let (at, mut ucmd) = at_and_ucmd!();
 
-    // Create empty files
-    at.touch("empty1.txt");
-    at.touch("empty2.txt");
+    at.write("file.txt", "content");
 
-    // Create archive
-    ucmd.args(&["-cf", "archive.tar", "empty1.txt", "empty2.txt"])
+    // Test standard -cf order
+    ucmd.args(&["-cf", "archive1.tar", "file.txt"]).succeeds();
+
+    assert!(at.file_exists("archive1.tar"));
+
+    // Test separate options
+    new_ucmd!()
+        .args(&["-c", "-f", "archive2.tar", "file.txt"])
+        .current_dir(at.as_string())
         .succeeds();
 
-    // Remove originals
-    at.remove("empty1.txt");
-    at.remove("empty2.txt");
+    assert!(at.file_exists("archive2.tar"));
+
+    // Test long form
+    new_ucmd!()
+        .args(&["--create", "--file", "archive3.tar", "file.txt"])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    assert!(at.file_exists("archive3.tar"));
+}
+
+#[test]
+fn test_extract_overwrites_existing_by_default() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    // Create original file and archive
+    at.write("file.txt", "original content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt"]).succeeds();
+
+    // Modify the file
+    at.write("file.txt", "modified content");
+
+    // Extract should overwrite
+    new_ucmd!()
+        .arg("-xf")
+        .arg(at.plus("archive.tar"))
+        .current_dir(at.as_string())
+        .succeeds();
+
+    // Verify original content is restored
+    assert_eq!(at.read("file.txt"), "original content");
+}
+
+#[test]
+fn test_extract_keep_old_files() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "original content");
+    at.write("other.txt", "other content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt", "other.txt"])
+        .succeeds();
+
+    at.write("file.txt", "modified content");
+    at.remove("other.txt");
+
+    new_ucmd!()
+        .args(&["-xkf", "archive.tar"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("file.txt: Cannot open: File exists");
+
+    // The existing file is kept, but the rest of the archive is extracted
+    assert_eq!(at.read("file.txt"), "modified content");
+    assert_eq!(at.read("other.txt"), "other content");
+}
+
+#[test]
+fn test_extract_skip_old_files() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "original content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt"]).succeeds();
+    at.write("file.txt", "modified content");
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--skip-old-files"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_stderr();
+
+    assert_eq!(at.read("file.txt"), "modified content");
+}
+
+#[test]
+fn test_extract_keep_newer_files() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "original content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt"]).succeeds();
+    at.write("file.txt", "modified content");
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--keep-newer-files"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stderr_contains("Current 'file.txt' is newer or same age");
+
+    assert_eq!(at.read("file.txt"), "modified content");
+}
+
+#[test]
+fn test_extract_overwrite_option_replaces_file() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "original content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt"]).succeeds();
+    at.write("file.txt", "modified content that is longer");
+
+    // The last overwrite option given wins
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "-k", "--overwrite"])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    assert_eq!(at.read("file.txt"), "original content");
+}
+
+#[test]
+fn test_extract_recursive_unlink_replaces_directory() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("target", "file content");
+    ucmd.args(&["-cf", "archive.tar", "target"]).succeeds();
+    at.remove("target");
+    at.mkdir_all("target/nested");
+    at.write("target/nested/old.txt", "old");
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2);
+    assert!(at.dir_exists("target"));
+
+    new_ucmd!()
+        .args(&["-xUf", "archive.tar", "--recursive-unlink"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.read("target"), "file content");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_no_overwrite_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("dir");
+    at.set_mode("dir", 0o755);
+    ucmd.args(&["-cf", "archive.tar", "dir"]).succeeds();
+    at.set_mode("dir", 0o700);
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--no-overwrite-dir"])
+        .current_dir(at.as_string())
+        .succeeds();
+    let mode = at.metadata("dir").permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds();
+    let mode = at.metadata("dir").permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+}
+
+#[test]
+fn test_extract_to_stdout() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("etc");
+    at.write("etc/app.conf", "key=value\n");
+    at.write("etc/other.conf", "other=1\n");
+    ucmd.args(&["-cf", "archive.tar", "etc/app.conf", "etc/other.conf"])
+        .succeeds();
+    at.remove("etc/app.conf");
+    at.remove("etc/other.conf");
+    at.rmdir("etc");
+
+    new_ucmd!()
+        .args(&["-xOf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("key=value\nother=1\n");
+
+    // Nothing is written to the filesystem
+    assert!(!at.dir_exists("etc"));
+}
+
+#[test]
+fn test_extract_to_stdout_verbose_names_go_to_stderr() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("dir");
+    at.write("dir/file.txt", "content");
+    ucmd.args(&["-czf", "archive.tar.gz", "dir"]).succeeds();
+
+    new_ucmd!()
+        .args(&["-xvzf", "archive.tar.gz", "--to-stdout"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("content")
+        .stderr_contains("dir/file.txt");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_to_command() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("logs");
+    at.write("logs/app.log", "line\n");
+    ucmd.args(&["-cf", "archive.tar", "logs"]).succeeds();
+    at.remove("logs/app.log");
+
+    new_ucmd!()
+        .args(&[
+            "-xf",
+            "archive.tar",
+            "--to-command",
+            "cat > \"$TAR_FILENAME.copy\"",
+        ])
+        .current_dir(at.as_string())
+        .succeeds();
+
+    assert!(!at.file_exists("logs/app.log"));
+    assert_eq!(at.read("logs/app.log.copy"), "line\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_to_command_environment() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.mkdir("dir");
+    at.write("dir/file.txt", "content");
+    at.set_mode("dir/file.txt", 0o640);
+    ucmd.args(&["-cf", "archive.tar", "dir"]).succeeds();
+
+    new_ucmd!()
+        .args(&[
+            "-xf",
+            "archive.tar",
+            "--strip-components=1",
+            "--to-command",
+            "echo \"$TAR_FILENAME $TAR_REALNAME $TAR_SIZE $TAR_MODE $TAR_FILETYPE\"; cat",
+        ])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is("file.txt dir/file.txt 7 0640 f\ncontent");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_to_command_failure() {
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("file.txt", "content");
+    ucmd.args(&["-cf", "archive.tar", "file.txt"]).succeeds();
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--to-command", "exit 3"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("Child returned status 3");
+
+    new_ucmd!()
+        .args(&[
+            "-xf",
+            "archive.tar",
+            "--to-command",
+            "exit 3",
+            "--ignore-command-error",
+        ])
+        .current_dir(at.as_string())
+        .succeeds();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_preserve_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (at, mut ucmd) = at_and_ucmd!();
+
+    at.write("tool", "#!/bin/sh\n");
+    at.set_mode("tool", 0o4750);
+    ucmd.args(&["-cf", "archive.tar", "tool"]).succeeds();
+    at.remove("tool");
+
+    new_ucmd!()
+        .args(&["-xpf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.metadata("tool").permissions().mode() & 0o7777, 0o4750);
+    at.remove("tool");
+
+    // The special bits are dropped and the umask applied
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--no-same-permissions"])
+        .current_dir(at.as_string())
+        .succeeds();
+    let mode = at.metadata("tool").permissions().mode() & 0o7777;
+    assert_eq!(mode & !0o750, 0, "unexpected mode {mode:o}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_restores_mtime_unless_touch() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let at = &at_and_ucmd!().0;
+
+    let mut tar_bytes = Vec::new();
+    {
+        let mut builder = TarRsBuilder::new(&mut tar_bytes);
+        let mut header = TarRsHeader::new_gnu();
+        header.set_mode(0o644);
+        header.set_mtime(1_000_000_000);
+        header.set_size("content".len() as u64);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "old.txt", Cursor::new("content"))
+            .unwrap();
+        builder.finish().unwrap();
+    }
+    at.write_bytes("archive.tar", &tar_bytes);
+    let archived = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+    new_ucmd!()
+        .args(&["-xf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.metadata("old.txt").modified().unwrap(), archived);
+
+    new_ucmd!()
+        .args(&["-xmf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert!(at.metadata("old.txt").modified().unwrap() > archived);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_delay_directory_restore() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let at = &at_and_ucmd!().0;
+
+    // The directory's contents are split up, as in an unsorted archive
+    let mut tar_bytes = Vec::new();
+    {
+        let mut builder = TarRsBuilder::new(&mut tar_bytes);
+        let mut header = TarRsHeader::new_gnu();
+        header.set_entry_type(tar_rs_crate::EntryType::Directory);
+        header.set_mode(0o750);
+        header.set_mtime(1_000_000_000);
+        header.set_size(0);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "dir/", std::io::empty())
+            .unwrap();
+        for name in ["dir/early.txt", "other.txt", "dir/late.txt"] {
+            let mut header = TarRsHeader::new_gnu();
+            header.set_mode(0o644);
+            header.set_size(0);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, std::io::empty())
+                .unwrap();
+        }
+        builder.finish().unwrap();
+    }
+    at.write_bytes("archive.tar", &tar_bytes);
+    let archived = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
 
-    // Extract
+    // By default the directory is restored once its members stop, so the
+    // late member updates its modification time again
     new_ucmd!()
-        .arg("-xf")
-        .arg(at.plus("archive.tar"))
+        .args(&["-xf", "archive.tar"])
         .current_dir(at.as_string())
         .succeeds();
+    assert!(at.file_exists("dir/late.txt"));
+    assert_eq!(at.metadata("dir").permissions().mode() & 0o777, 0o750);
+    assert_ne!(at.metadata("dir").modified().unwrap(), archived);
 
-    // Verify empty files exist and are still empty
-    assert!(at.file_exists("empty1.txt"));
-    assert!(at.file_exists("empty2.txt"));
-    assert_eq!(at.read("empty1.txt"), "");
-    assert_eq!(at.read("empty2.txt"), "");
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--delay-directory-restore"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.metadata("dir").modified().unwrap(), archived);
 }
 
-// Error handling and exit code tests
-
-#[test]
 #[cfg(unix)]
-fn test_create_permission_denied() {
-    if rustix::process::geteuid().is_root() {
-        eprintln!("skipping: running as root");
+#[test]
+fn test_extract_same_owner() {
+    use std::os::unix::fs::MetadataExt;
+
+    if !rustix::process::geteuid().is_root() {
+        eprintln!("skipping: not running as root");
         return;
     }
 
-    let (at, mut ucmd) = at_and_ucmd!();
+    let at = &at_and_ucmd!().0;
 
-    at.write("file.txt", "content");
-    at.mkdir("readonly");
+    let mut tar_bytes = Vec::new();
+    {
+        let mut builder = TarRsBuilder::new(&mut tar_bytes);
+        let mut header = TarRsHeader::new_gnu();
+        header.set_mode(0o644);
+        header.set_uid(4321);
+        header.set_gid(4321);
+        header.set_username("root").unwrap();
+        header.set_groupname("root").unwrap();
+        header.set_size(0);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "owned.txt", std::io::empty())
+            .unwrap();
+        builder.finish().unwrap();
+    }
+    at.write_bytes("archive.tar", &tar_bytes);
 
-    // Make directory read-only
-    at.set_readonly("readonly");
+    // Names win over the numeric ids by default
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--same-owner"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.metadata("owned.txt").uid(), 0);
 
-    ucmd.args(&["-cf", "readonly/archive.tar", "file.txt"])
-        .fails()
-        .code_is(2)
-        .stderr_contains("readonly/archive.tar");
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--numeric-owner"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.metadata("owned.txt").uid(), 4321);
+    assert_eq!(at.metadata("owned.txt").gid(), 4321);
 
-    // Cleanup - restore permissions so test cleanup can work
-    at.set_mode("readonly", 0o755);
+    new_ucmd!()
+        .args(&["-xf", "archive.tar", "--numeric-owner", "--no-same-owner"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert_eq!(at.metadata("owned.txt").uid(), 0);
 }
 
+#[cfg(unix)]
 #[test]
-fn test_extract_corrupted_archive() {
-    let (at, mut ucmd) = at_and_ucmd!();
-
-    // Create a corrupted tar file (invalid header)
-    at.write("corrupted.tar", "This is not a valid tar file content");
+fn test_roundtrip_hard_links() {
+    use std::os::unix::fs::MetadataExt;
 
-    ucmd.args(&["-xf", "corrupted.tar"]).fails().code_is(2);
-}
-
-#[test]
-fn test_create_with_dash_in_filename() {
     let (at, mut ucmd) = at_and_ucmd!();
 
-    // Create files starting with dash
-    at.write("-dash-file.txt", "content with dash");
-    at.write("normal.txt", "normal content");
-
-    ucmd.args(&["-cf", "archive.tar", "--", "-dash-file.txt", "normal.txt"])
-        .succeeds();
-
-    assert!(at.file_exists("archive.tar"));
-
-    // Verify extraction works
-    at.remove("-dash-file.txt");
-    at.remove("normal.txt");
+    at.mkdir("dir");
+    at.write("dir/a.txt", "shared content");
+    at.hard_link("dir/a.txt", "dir/b.txt");
+    ucmd.args(&["-cf", "archive.tar", "dir"]).succeeds();
 
+    at.mkdir("out");
     new_ucmd!()
-        .arg("-xf")
-        .arg(at.plus("archive.tar"))
+        .args(&["-xf", "archive.tar", "-C", "out"])
         .current_dir(at.as_string())
         .succeeds();
 
-    assert!(at.file_exists("-dash-file.txt"));
-    assert_eq!(at.read("-dash-file.txt"), "content with dash");
+    let a = std::fs::metadata(at.plus("out/dir/a.txt")).unwrap();
+    let b = std::fs::metadata(at.plus("out/dir/b.txt")).unwrap();
+    assert_eq!(a.ino(), b.ino());
+    assert_eq!(a.nlink(), 2);
+    assert_eq!(at.read("out/dir/b.txt"), "shared content");
 }
 
-// CLI argument handling tests
-
 #[test]
-fn test_mixed_short_and_long_options() {
+fn test_roundtrip_listed_incremental() {
     let (at, mut ucmd) = at_and_ucmd!();
 
-    at.write("file.txt", "content");
+    at.mkdir_all("data/sub");
+    at.write("data/keep.txt", "keep");
+    at.write("data/gone.txt", "gone");
+    at.write("data/sub/inner.txt", "inner");
 
-    // Test mixing -x with --file
-    ucmd.args(&["-c", "--file=archive.tar", "file.txt"])
+    ucmd.args(&["-cf", "level0.tar", "-g", "data.snar", "data"])
         .succeeds();
+    assert!(at.read("data.snar").starts_with("GNU tar-"));
 
-    assert!(at.file_exists("archive.tar"));
+    // Past the granularity of file timestamps
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    at.remove("data/gone.txt");
+    at.write("data/sub/new.txt", "new");
+    new_ucmd!()
+        .args(&["-cf", "level1.tar", "-g", "data.snar", "data"])
+        .current_dir(at.as_string())
+        .succeeds();
 
-    at.remove("file.txt");
+    // Level 1 holds only the changes, listing each directory
+    let mut archive = TarRsArchive::new(Cursor::new(at.read_bytes("level1.tar")));
+    let members: Vec<_> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let mut entry = entry.unwrap();
+            let kind = entry.header().entry_type().as_byte();
+            let name = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            (kind, name, data)
+        })
+        .collect();
+    assert_eq!(
+        members,
+        [
+            (b'D', "data/".to_string(), b"Nkeep.txt\0Dsub\0\0".to_vec()),
+            (
+                b'D',
+                "data/sub/".to_string(),
+                b"Ninner.txt\0Ynew.txt\0\0".to_vec()
+            ),
+            (b'0', "data/sub/new.txt".to_string(), b"new".to_vec()),
+        ]
+    );
 
-    // Test extraction with mixed options
+    // Restoring the chain reproduces the final state, deletions included
+    at.mkdir("out");
+    for archive in ["level0.tar", "level1.tar"] {
+        new_ucmd!()
+            .args(&["-xf", archive, "-g", "/dev/null", "-C", "out"])
+            .current_dir(at.as_string())
+            .succeeds();
+    }
+    assert_eq!(at.read("out/data/keep.txt"), "keep");
+    assert_eq!(at.read("out/data/sub/inner.txt"), "inner");
+    assert_eq!(at.read("out/data/sub/new.txt"), "new");
+    assert!(!at.file_exists("out/data/gone.txt"));
+
+    // Without -g, dumpdir members are plain directories
+    at.mkdir("plain");
     new_ucmd!()
-        .args(&["-x", "--file", "archive.tar"])
+        .args(&["-xf", "level0.tar", "-C", "plain"])
         .current_dir(at.as_string())
         .succeeds();
-
-    assert!(at.file_exists("file.txt"));
+    new_ucmd!()
+        .args(&["-xf", "level1.tar", "-C", "plain"])
+        .current_dir(at.as_string())
+        .succeeds();
+    assert!(at.file_exists("plain/data/gone.txt"));
 }
 
 #[test]
-fn test_option_order_variations() {
+fn test_listed_incremental_bad_snapshot() {
     let (at, mut ucmd) = at_and_ucmd!();
 
     at.write("file.txt", "content");
+    at.write("bad.snar", "not a snapshot\n");
+    ucmd.args(&["-cf", "archive.tar", "-g", "bad.snar", "file.txt"])
+        .fails()
+        .code_is(2)
+        .stderr_contains("bad.snar: Bad incremental file format");
+}
 
-    // Test standard -cf order
-    ucmd.args(&["-cf", "archive1.tar", "file.txt"]).succeeds();
+#[test]
+fn test_roundtrip_multi_volume() {
+    let (at, mut ucmd) = at_and_ucmd!();
 
-    assert!(at.file_exists("archive1.tar"));
+    let data: Vec<u8> = (0..30_000u32).map(|n| (n % 251) as u8).collect();
+    at.mkdir("data");
+    at.write_bytes("data/big.bin", &data);
+    at.write("data/small.txt", "small");
+
+    // 10 KiB volumes: the big file straddles all four
+    ucmd.args(&[
+        "-cM",
+        "-L",
+        "10",
+        "-f",
+        "v1.tar",
+        "-f",
+        "v2.tar",
+        "-f",
+        "v3.tar",
+        "-f",
+        "v4.tar",
+        "--sort=name",
+        "data",
+    ])
+    .succeeds()
+    .no_stderr();
+
+    for volume in ["v1.tar", "v2.tar", "v3.tar"] {
+        assert_eq!(at.read_bytes(volume).len(), 10 * 1024);
+    }
+    // The second volume resumes big.bin with a GNU continuation header
+    let v2 = at.read_bytes("v2.tar");
+    let continued = TarRsHeader::from_byte_slice(&v2[..TAR_BLOCK_SIZE]);
+    assert_eq!(continued.entry_type().as_byte(), b'M');
+    assert_eq!(continued.path().unwrap().to_str(), Some("data/big.bin"));
+    assert_eq!(&continued.as_gnu().unwrap().offset[..11], b"00000022000");
 
-    // Test separate options
     new_ucmd!()
-        .args(&["-c", "-f", "archive2.tar", "file.txt"])
+        .args(&[
+            "-tM", "-f", "v1.tar", "-f", "v2.tar", "-f", "v3.tar", "-f", "v4.tar",
+        ])
         .current_dir(at.as_string())
-        .succeeds();
+        .succeeds()
+        .stdout_is("data/\ndata/big.bin\ndata/small.txt\n");
 
-    assert!(at.file_exists("archive2.tar"));
+    at.mkdir("out");
+    new_ucmd!()
+        .args(&[
+            "-xM", "-f", "v1.tar", "-f", "v2.tar", "-f", "v3.tar", "-f", "v4.tar", "-C", "out",
+        ])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_stderr();
+    assert_eq!(at.read_bytes("out/data/big.bin"), data);
+    assert_eq!(at.read("out/data/small.txt"), "small");
 
-    // Test long form
+    // A missing volume is an error rather than a short file
     new_ucmd!()
-        .args(&["--create", "--file", "archive3.tar", "file.txt"])
+        .args(&["-xM", "-f", "v1.tar", "-f", "v2.tar", "-C", "out"])
         .current_dir(at.as_string())
-        .succeeds();
+        .fails()
+        .code_is(2)
+        .stderr_contains("Cannot extract 'data/big.bin'");
+}
 
-    assert!(at.file_exists("archive3.tar"));
+#[test]
+fn test_multi_volume_from_gnu() {
+    // tests/fixtures/tar: `src/big` split by GNU tar 1.34 with `-M -L 10`,
+    // whose continuation header has no magic
+    let (at, mut ucmd) = at_and_ucmd!();
+    let data: Vec<u8> = (0..15_000u32).map(|n| (n % 251) as u8).collect();
+
+    ucmd.args(&["-tvf", "gnu-volume-2.tar"])
+        .succeeds()
+        .stdout_contains("src/big--Continued at byte 9216--");
+
+    at.mkdir("out");
+    new_ucmd!()
+        .args(&[
+            "-xM",
+            "-f",
+            "gnu-volume-1.tar",
+            "-f",
+            "gnu-volume-2.tar",
+            "-C",
+            "out",
+        ])
+        .current_dir(at.as_string())
+        .succeeds()
+        .no_stderr();
+    assert_eq!(at.read_bytes("out/src/big"), data);
 }
 
+#[cfg(unix)]
 #[test]
-fn test_extract_overwrites_existing_by_default() {
+fn test_roundtrip_multi_volume_info_script() {
     let (at, mut ucmd) = at_and_ucmd!();
 
-    // Create original file and archive
-    at.write("file.txt", "original content");
-    ucmd.args(&["-cf", "archive.tar", "file.txt"]).succeeds();
+    at.write_bytes("big.bin", &[7; 20_000]);
+    // Moves each full volume aside, the way a tape would be changed
+    at.write(
+        "next-volume.sh",
+        "echo \"$TAR_SUBCOMMAND $TAR_VOLUME $TAR_ARCHIVE\" >> volumes.log\n\
+         mv \"$TAR_ARCHIVE\" part$((TAR_VOLUME - 1)).tar\n",
+    );
 
-    // Modify the file
-    at.write("file.txt", "modified content");
+    ucmd.args(&[
+        "-c",
+        "-L",
+        "10",
+        "-F",
+        "sh next-volume.sh",
+        "-f",
+        "archive.tar",
+        "big.bin",
+    ])
+    .succeeds()
+    .no_stderr();
+    assert_eq!(
+        at.read("volumes.log"),
+        "-c 2 archive.tar\n-c 3 archive.tar\n"
+    );
 
-    // Extract should overwrite
+    at.mkdir("out");
     new_ucmd!()
-        .arg("-xf")
-        .arg(at.plus("archive.tar"))
+        .args(&[
+            "-xM",
+            "-f",
+            "part1.tar",
+            "-f",
+            "part2.tar",
+            "-f",
+            "archive.tar",
+            "-C",
+            "out",
+        ])
         .current_dir(at.as_string())
         .succeeds();
+    assert_eq!(at.read_bytes("out/big.bin"), vec![7; 20_000]);
 
-    // Verify original content is restored
-    assert_eq!(at.read("file.txt"), "original content");
+    // A failing script stops the archive
+    new_ucmd!()
+        .args(&[
+            "-c",
+            "-L",
+            "10",
+            "-F",
+            "false",
+            "-f",
+            "archive.tar",
+            "big.bin",
+        ])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("'false' command failed");
 }
 
 // Edge case tests
@@ -870,24 +2905,22 @@ fn test_posix_and_dash_prefix_both_work() {
 }
 
 #[test]
-fn test_posix_b_matches_dash_prefix_failure() {
+fn test_posix_b_matches_dash_prefix() {
     let (at, mut ucmd) = at_and_ucmd!();
 
     at.write("file.txt", "hello");
 
-    // Dash-prefixed form currently rejects '-b'.
     new_ucmd!()
-        .args(&["-cbf", "20", "dash.tar", "file.txt"])
+        .args(&["-cb", "20", "-f", "dash.tar", "file.txt"])
         .current_dir(at.as_string())
-        .fails()
-        .code_is(64)
-        .stderr_contains("unexpected argument '-b'");
+        .succeeds();
 
-    // POSIX keystring form should fail with the same unsupported option.
+    // POSIX keystring form takes the blocking factor from the operands too
     ucmd.args(&["cbf", "20", "posix.tar", "file.txt"])
-        .fails()
-        .code_is(64)
-        .stderr_contains("unexpected argument '-b'");
+        .succeeds();
+
+    assert_eq!(at.read_bytes("dash.tar").len(), 20 * TAR_BLOCK_SIZE);
+    assert_eq!(at.read_bytes("dash.tar"), at.read_bytes("posix.tar"));
 }
 
 // List operation tests
@@ -944,6 +2977,25 @@ fn test_list_directory() {
         .stdout_contains("testdir/subdir/file2.txt");
 }
 
+#[test]
+fn test_list_directory_members_end_in_slash() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.mkdir("dir/sub");
+    at.write("dir/sub/file.txt", "content");
+    let listing = "dir/\ndir/sub/\ndir/sub/file.txt\n";
+
+    // What -v shows on create is what the archive holds
+    ucmd.args(&["-cvf", "archive.tar", "dir"])
+        .succeeds()
+        .stdout_is(listing);
+    new_ucmd!()
+        .args(&["-tf", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is(listing);
+}
+
 #[test]
 fn test_list_verbose() {
     let (at, mut ucmd) = at_and_ucmd!();
@@ -958,6 +3010,162 @@ fn test_list_verbose() {
         .stdout_contains("7 "); // verbose output includes file size; absent from plain -t listing
 }
 
+#[test]
+fn test_list_verbose_matches_gnu_format() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    let mut tar_bytes = Vec::new();
+    {
+        let mut builder = TarRsBuilder::new(&mut tar_bytes);
+        let mut header = TarRsHeader::new_gnu();
+        header.set_mode(0o644);
+        header.set_uid(1000);
+        header.set_gid(100);
+        header.set_username("alice").unwrap();
+        header.set_groupname("staff").unwrap();
+        header.set_mtime(1_700_000_000);
+        header.set_size(5);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "file.txt", Cursor::new("hello"))
+            .unwrap();
+
+        let mut header = TarRsHeader::new_gnu();
+        header.set_entry_type(tar_rs_crate::EntryType::Symlink);
+        header.set_mode(0o777);
+        header.set_uid(1000);
+        header.set_gid(100);
+        header.set_mtime(1_700_000_000);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "link", "file.txt")
+            .unwrap();
+        builder.finish().unwrap();
+    }
+    at.write_bytes("archive.tar", &tar_bytes);
+
+    ucmd.args(&["-tv", "--utc", "-f", "archive.tar"])
+        .succeeds()
+        .stdout_is(
+            "-rw-r--r-- alice/staff       5 2023-11-14 22:13 file.txt\n\
+             lrwxrwxrwx 1000/100          0 2023-11-14 22:13 link -> file.txt\n",
+        );
+
+    new_ucmd!()
+        .args(&["-tvv", "--utc", "--full-time", "--numeric-owner"])
+        .args(&["-f", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is(
+            "-rw-r--r-- 1000/100          5 2023-11-14 22:13:20 file.txt\n\
+             lrwxrwxrwx 1000/100          0 2023-11-14 22:13:20 link -> file.txt\n",
+        );
+}
+
+#[test]
+fn test_list_json() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.write("dir/file.txt", "hello");
+    ucmd.args(&["-cf", "archive.tar", "dir"]).succeeds();
+
+    let result = new_ucmd!()
+        .args(&["-t", "--list-format=json", "-f", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds();
+    let lines: Vec<&str> = result.stdout_str().lines().collect();
+    assert_eq!(lines.len(), 2, "one object per member: {lines:?}");
+    assert!(lines[0].starts_with(r#"{"path":"dir/","type":"directory","#));
+    assert!(lines[1].starts_with(r#"{"path":"dir/file.txt","type":"file","#));
+    assert!(lines[1].contains(r#""size":5,"#));
+    assert!(lines.iter().all(|line| line.ends_with('}')));
+
+    new_ucmd!()
+        .args(&["-t", "--list-format=yaml", "-f", "archive.tar"])
+        .current_dir(at.as_string())
+        .fails();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_mtree_spec() {
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.mkdir("dir");
+    at.write("dir/file", "hello\n");
+    at.symlink_file("file", "dir/soft");
+    let expected = "#mtree\n\
+        ./dir time=1700000000.0 mode=755 gid=100 uid=1000 type=dir\n\
+        ./dir/file time=1700000000.0 mode=644 gid=100 uid=1000 type=file size=6 \
+        sha256digest=5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03\n\
+        ./dir/soft time=1700000000.0 mode=755 gid=100 uid=1000 type=link link=file\n";
+    let settings = [
+        "--owner=1000",
+        "--group=100",
+        "--mode=u=rwX,go=rX",
+        "--mtime=@1700000000",
+        "--sort=name",
+    ];
+
+    // From the filesystem
+    ucmd.args(&["-c", "--format=mtree", "-f", "-"])
+        .args(&settings)
+        .arg("dir")
+        .succeeds()
+        .stdout_is(expected);
+
+    // From an archive of the same files
+    new_ucmd!()
+        .args(&["-cf", "archive.tar"])
+        .args(&settings)
+        .arg("dir")
+        .current_dir(at.as_string())
+        .succeeds();
+    new_ucmd!()
+        .args(&["-t", "--list-format=mtree", "-f", "archive.tar"])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_is(expected);
+
+    new_ucmd!()
+        .args(&["-cz", "--format=mtree", "-f", "spec.mtree", "dir"])
+        .current_dir(at.as_string())
+        .fails()
+        .code_is(2)
+        .stderr_contains("Cannot use compression with --format=mtree");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_mtree_spec_keeps_nanoseconds() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let (at, mut ucmd) = at_and_ucmd!();
+    at.write("file", "hello\n");
+    std::fs::File::options()
+        .write(true)
+        .open(at.plus("file"))
+        .unwrap()
+        .set_modified(UNIX_EPOCH + Duration::new(1_700_000_000, 833_304_333))
+        .unwrap();
+
+    // As bsdtar writes it, from the time on disk
+    ucmd.args(&["-c", "--format=mtree", "-f", "-", "file"])
+        .succeeds()
+        .stdout_contains("./file time=1700000000.833304333 mode=");
+    // `--mtime` times are whole seconds
+    new_ucmd!()
+        .args(&[
+            "-c",
+            "--format=mtree",
+            "-f",
+            "-",
+            "--mtime=@1700000000",
+            "file",
+        ])
+        .current_dir(at.as_string())
+        .succeeds()
+        .stdout_contains("./file time=1700000000.0 mode=");
+}
+
 #[test]
 fn test_list_nonexistent_archive() {
     new_ucmd!()